@@ -0,0 +1,140 @@
+// Moving finished items out of a list into a sibling `<name>.archive.todo`
+// keeping the titles of their ancestors so they can be restored later
+
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+
+use errors::*;
+use todo_list::{TodoList, TodoItem};
+
+const ARCHIVE_EXTENSION: &str = ".archive.todo";
+
+pub fn is_archive(path: &str) -> bool {
+    path.ends_with(ARCHIVE_EXTENSION)
+}
+
+pub fn archive_path(path: &str) -> String {
+    format!("{}{}", path.strip_suffix(".todo").unwrap_or(path), ARCHIVE_EXTENSION)
+}
+
+pub fn original_path(archive: &str) -> String {
+    format!("{}.todo", archive.strip_suffix(ARCHIVE_EXTENSION).unwrap_or(archive))
+}
+
+pub fn load_archive(list: &TodoList) -> Result<TodoList> {
+    let path = archive_path(&list.path);
+    if Path::new(&path).exists() {
+        TodoList::load(Path::new(&path))
+    } else {
+        Ok(TodoList::create(format!("{} (archive)", list.name), path))
+    }
+}
+
+// Items finished at an unknown time never count as before a date
+fn should_archive(item: &TodoItem, before: Option<DateTime<Local>>) -> bool {
+    item.ticked_off && match before {
+        Some(date) => item.completed.is_some_and(|x| x < date),
+        None => true,
+    }
+}
+
+// Removes finished items (with their children) and pairs them with their ancestor titles
+fn take_finished(items: &mut Vec<TodoItem>, before: Option<DateTime<Local>>,
+                 ancestors: &mut Vec<String>, res: &mut Vec<(Vec<String>, TodoItem)>) {
+    let mut i = 0;
+    while i < items.len() {
+        if should_archive(&items[i], before) {
+            res.push((ancestors.clone(), items.remove(i)));
+        } else {
+            ancestors.push(items[i].title.clone());
+            take_finished(&mut items[i].contents, before, ancestors, res);
+            ancestors.pop();
+            i += 1;
+        }
+    }
+}
+
+// Finds (creating if needed) the children of the item with the given ancestor titles
+fn find_or_create<'a>(items: &'a mut Vec<TodoItem>, ancestors: &[String]) -> &'a mut Vec<TodoItem> {
+    match ancestors.split_first() {
+        None => items,
+        Some((title, rest)) => {
+            let pos = match items.iter().position(|x| &x.title == title) {
+                Some(pos) => pos,
+                None => {
+                    items.push(TodoItem::create(title.clone()));
+                    items.len() - 1
+                },
+            };
+            find_or_create(&mut items[pos].contents, rest)
+        },
+    }
+}
+
+pub fn insert_under(list: &mut TodoList, ancestors: &[String], item: TodoItem) {
+    find_or_create(&mut list.contents, ancestors).push(item);
+}
+
+// Saves both lists, the archive goes first so a failure can't lose items
+pub fn archive_finished(list: &mut TodoList, before: Option<DateTime<Local>>) -> Result<usize> {
+    let mut taken = vec![];
+    take_finished(&mut list.contents, before, &mut vec![], &mut taken);
+    let count = taken.len();
+    if count == 0 { return Ok(0); }
+
+    let mut archive = load_archive(list)?;
+    for (ancestors, item) in taken {
+        insert_under(&mut archive, &ancestors, item);
+    }
+    archive.save()?;
+    list.save()?;
+    Ok(count)
+}
+
+// Removes the item at `path` returning it with its ancestor titles
+fn take_item(list: &mut TodoList, path: &[usize]) -> Result<(Vec<String>, TodoItem)> {
+    let mut ancestors = vec![];
+    for depth in 1 .. path.len() {
+        match list.item_at(&path[..depth]) {
+            Some(item) => ancestors.push(item.title.clone()),
+            None => bail!("Invalid item"),
+        }
+    }
+    match list.take_at(path) {
+        Some(item) => Ok((ancestors, item)),
+        None => bail!("Invalid item"),
+    }
+}
+
+// Archives a single item, saving both lists like `archive_finished`
+pub fn archive_item(list: &mut TodoList, path: &[usize]) -> Result<()> {
+    let (ancestors, item) = take_item(list, path)?;
+    let mut archive = load_archive(list)?;
+    insert_under(&mut archive, &ancestors, item);
+    archive.save()?;
+    list.save()?;
+    Ok(())
+}
+
+// Moves the item at `path` in the archive back into the original list
+// (`list`, which the archive belongs to), empty ancestors left behind in the archive are removed.
+// Both are saved afterwards
+pub fn restore(archive: &mut TodoList, list: &mut TodoList, path: &[usize]) -> Result<()> {
+    let (ancestors, item) = take_item(archive, path)?;
+    let parent_path = &path[..path.len() - 1];
+
+    for depth in (0 .. parent_path.len()).rev() {
+        let now_empty = archive.item_at(&parent_path[..depth + 1])
+            .is_some_and(|x| x.contents.is_empty() && !x.ticked_off);
+        if !now_empty { break; }
+        if let Some(items) = archive.contents_at(&parent_path[..depth]) {
+            items.remove(parent_path[depth]);
+        }
+    }
+
+    insert_under(list, &ancestors, item);
+    list.save()?;
+    archive.save()?;
+    Ok(())
+}
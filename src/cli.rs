@@ -5,6 +5,12 @@ pub fn get_app() -> App<'static, 'static> {
         .version("0.1.0")
         .author("Braedon Wooding <braedonww@gmail.com>")
         .about("A todo manager written in Rust")
+        .arg(Arg::with_name("no-color")
+            .long("no-color")
+            .help("Don't use any colours (the same as setting NO_COLOR)"))
+        .arg(Arg::with_name("ascii")
+            .long("ascii")
+            .help("Only draw ASCII characters (the default when the locale isn't UTF-8)"))
         .subcommand(SubCommand::with_name("open")
             .about("Open a todo list")
             .arg(Arg::with_name("FILE")
@@ -17,6 +23,43 @@ pub fn get_app() -> App<'static, 'static> {
                 .help("The filename")
                 .default_value("list.todo")
                 .required(true)))
+        .subcommand(SubCommand::with_name("archive")
+            .about("Move ticked off items into the list's .archive.todo file")
+            .arg(Arg::with_name("FILE")
+                .help("The list to archive")
+                .default_value("list.todo")
+                .required(true))
+            .arg(Arg::with_name("before")
+                .long("before")
+                .takes_value(true)
+                .value_name("DATE")
+                .help("Only archive items completed before this date (YYYY-MM-DD)")))
+        .subcommand(SubCommand::with_name("tick")
+            .about("Tick off an item, recurring items get their next occurrence")
+            .arg(Arg::with_name("FILE")
+                .help("The list the item is in")
+                .required(true))
+            .arg(Arg::with_name("ITEM")
+                .help("The item as a 1-indexed path i.e. 2.1 for the first child of the second item")
+                .required(true)))
+        .subcommand(SubCommand::with_name("summary")
+            .about("Print a list with its progress and estimated time")
+            .arg(Arg::with_name("FILE")
+                .help("The list to print")
+                .default_value("list.todo")
+                .required(true)))
+        .subcommand(SubCommand::with_name("report")
+            .about("Reports on your lists")
+            .subcommand(SubCommand::with_name("time")
+                .about("Summarise the time logged per list and item")
+                .arg(Arg::with_name("FILE")
+                    .help("The lists to report on (defaults to every list in ~/_todo_lists/ and ./)")
+                    .multiple(true))
+                .arg(Arg::with_name("since")
+                    .long("since")
+                    .takes_value(true)
+                    .value_name("DATE")
+                    .help("Only count time logged since this date (YYYY-MM-DD)"))))
         .subcommand(SubCommand::with_name("completions")
             .about("Generates completion scripts for your shell")
             .arg(Arg::with_name("SHELL")
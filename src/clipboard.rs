@@ -0,0 +1,43 @@
+// Copying items to the system clipboard with the OSC 52 escape sequence,
+// which most terminals (and tmux/ssh) pass through to the clipboard
+
+use todo_list::TodoItem;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut res = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize;
+        for i in 0 .. 4 {
+            if i <= chunk.len() {
+                res.push(BASE64[n >> (18 - 6 * i) & 0x3f] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
+fn write_item(item: &TodoItem, depth: usize, res: &mut String) {
+    res.push_str(&format!("{}- [{}] {}\n", "  ".repeat(depth), if item.ticked_off {"x"} else {" "}, item.title));
+    for child in item.contents.iter() {
+        write_item(child, depth + 1, res);
+    }
+}
+
+// Markdown style checklist, children are indented by two spaces
+pub fn to_text(items: &[TodoItem]) -> String {
+    let mut res = String::new();
+    for item in items.iter() {
+        write_item(item, 0, &mut res);
+    }
+    res
+}
+
+// Written to the terminal to set the clipboard to `text`
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
@@ -0,0 +1,97 @@
+// User preferences, read from `~/_todo_lists/config.toml`
+
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use toml;
+
+use errors::*;
+use util;
+
+// An item status, `done` statuses count as ticked off
+#[derive(Deserialize, Clone, Debug)]
+pub struct StatusDef {
+    pub name: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+impl StatusDef {
+    fn new(name: &str, symbol: &str, done: bool) -> StatusDef {
+        StatusDef {
+            name: name.to_owned(),
+            symbol: symbol.to_owned(),
+            done,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Config {
+    // extra statuses (or overrides of the builtin ones by name)
+    #[serde(default)]
+    pub statuses: Vec<StatusDef>,
+    // parents tick themselves when all their children are ticked (and untick when one isn't)
+    #[serde(default)]
+    pub auto_tick_parents: bool,
+    // progress is weighted by estimated time instead of by item
+    #[serde(default)]
+    pub estimate_weighted_progress: bool,
+    // finished recurring items go to the archive rather than staying in the list
+    #[serde(default)]
+    pub archive_recurring: bool,
+    // yanked and cut items are also copied to the system clipboard (through OSC 52)
+    #[serde(default)]
+    pub osc52_clipboard: bool,
+    // completed items (and subtrees) start off hidden, 'F' toggles it
+    #[serde(default)]
+    pub hide_completed: bool,
+    // the name of a builtin (default, light, dark, mono) or custom theme
+    #[serde(default)]
+    pub theme: Option<String>,
+    // custom themes by name, i.e. `[themes.mine]` with `base = "dark"` and `tag = "bold #00afaf"`
+    #[serde(default)]
+    pub themes: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Config {
+    pub fn config_path() -> Result<PathBuf> {
+        let mut path = util::get_file_path()?;
+        path.push("config.toml");
+        Ok(path)
+    }
+
+    // No config file is fine, a broken one isn't
+    pub fn load() -> Result<Config> {
+        let path = Self::config_path()?;
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        toml::from_str(&read_to_string(&path)?).chain_err(|| "Failed to load config")
+    }
+
+    // All statuses in the order they are cycled through
+    pub fn statuses(&self) -> Vec<StatusDef> {
+        let mut res = vec![
+            StatusDef::new("todo", " ", false),
+            StatusDef::new("in-progress", "~", false),
+            StatusDef::new("waiting", "…", false),
+            StatusDef::new("blocked", "!", false),
+            StatusDef::new("done", "✓", true),
+            StatusDef::new("cancelled", "✗", true),
+        ];
+        for status in self.statuses.iter() {
+            match res.iter().position(|x| x.name == status.name) {
+                Some(pos) => res[pos] = status.clone(),
+                None => res.push(status.clone()),
+            }
+        }
+        res
+    }
+
+    pub fn status(&self, name: &str) -> Option<StatusDef> {
+        self.statuses().into_iter().find(|x| x.name == name)
+    }
+}
@@ -0,0 +1,112 @@
+// Items can depend on other items (potentially in other lists)
+// an item is referred to by its id, "id" for the same list or "path/to/list.todo#id"
+
+use std::fmt;
+
+use chrono::Local;
+
+use todo_list::{TodoList, TodoItem};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    // None is the list the reference is in
+    pub list: Option<String>,
+    pub id: String,
+}
+
+impl Reference {
+    pub fn parse(text: &str) -> Reference {
+        match text.rfind('#') {
+            Some(pos) => Reference {
+                list: Some(text[..pos].to_owned()),
+                id: text[pos + 1..].to_owned(),
+            },
+            None => Reference {
+                list: None,
+                id: text.to_owned(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.list {
+            Some(ref list) => write!(f, "{}#{}", list, self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+fn contains_id(items: &[TodoItem], id: &str) -> bool {
+    items.iter().any(|x| x.id.as_ref().is_some_and(|x| x == id) || contains_id(&x.contents, id))
+}
+
+pub fn collect_ids(items: &[TodoItem], res: &mut Vec<String>) {
+    for item in items.iter() {
+        if let Some(ref id) = item.id {
+            res.push(id.clone());
+        }
+        collect_ids(&item.contents, res);
+    }
+}
+
+// Short ids that are unique in the list
+pub fn new_id(list: &TodoList) -> String {
+    let mut seed = Local::now().timestamp_nanos_opt().unwrap_or(0) as u64;
+    loop {
+        // xorshift to spread out ids made close together
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let id = format!("{:06x}", seed & 0xff_ffff);
+        if !contains_id(&list.contents, &id) {
+            return id;
+        }
+    }
+}
+
+// Gives the item at `path` an id if it doesn't have one
+pub fn ensure_id(list: &mut TodoList, path: &[usize]) -> Option<String> {
+    if let Some(id) = list.item_at(path)?.id.clone() {
+        return Some(id);
+    }
+    let id = new_id(list);
+    list.item_at(path)?.id = Some(id.clone());
+    Some(id)
+}
+
+// Clears the ids in `item` that are already used in `items`, i.e. before pasting a copy
+pub fn clear_taken_ids(items: &[TodoItem], item: &mut TodoItem) {
+    if item.id.as_ref().is_some_and(|x| contains_id(items, x)) {
+        item.id = None;
+    }
+    for child in item.contents.iter_mut() {
+        clear_taken_ids(items, child);
+    }
+}
+
+pub fn find_path(items: &[TodoItem], id: &str) -> Option<Vec<usize>> {
+    for (i, item) in items.iter().enumerate() {
+        if item.id.as_ref().is_some_and(|x| x == id) {
+            return Some(vec![i]);
+        }
+        if let Some(mut path) = find_path(&item.contents, id) {
+            path.insert(0, i);
+            return Some(path);
+        }
+    }
+    None
+}
+
+pub fn find_item<'a>(items: &'a [TodoItem], id: &str) -> Option<&'a TodoItem> {
+    for item in items.iter() {
+        if item.id.as_ref().is_some_and(|x| x == id) {
+            return Some(item);
+        }
+        if let Some(item) = find_item(&item.contents, id) {
+            return Some(item);
+        }
+    }
+    None
+}
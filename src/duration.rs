@@ -0,0 +1,63 @@
+// Durations (in minutes) written like "2h", "30m", "1d 4h" or "1.5h"
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+// a day of work rather than a calendar day
+pub const HOURS_PER_DAY: f64 = 8.0;
+
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut found = false;
+    for ch in text.trim().chars() {
+        if ch.is_ascii_digit() || ch == '.' {
+            number.push(ch);
+            continue;
+        }
+        let minutes = match ch.to_ascii_lowercase() {
+            'd' => 60.0 * HOURS_PER_DAY,
+            'h' => 60.0,
+            'm' => 1.0,
+            ' ' if number.is_empty() => continue,
+            _ => return None,
+        };
+        total += number.parse::<f64>().ok()? * minutes;
+        number.clear();
+        found = true;
+    }
+    if !number.is_empty() {
+        // a bare number is in hours, but a number after a unit (i.e. "2h30") is ambiguous
+        if found { return None; }
+        total += number.parse::<f64>().ok()? * 60.0;
+        found = true;
+    }
+    if found {Some(total.round() as u64)} else {None}
+}
+
+// Only uses hours and minutes so it is never ambiguous
+pub fn format_duration(minutes: u64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
+    }
+}
+
+// For use with `#[serde(with = "duration::optional")]`
+pub mod optional {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match *value {
+            Some(minutes) => serializer.serialize_str(&format_duration(minutes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+        use serde::de::Error;
+        let text = String::deserialize(deserializer)?;
+        parse_duration(&text).map(Some)
+            .ok_or_else(|| D::Error::custom(format!("invalid duration '{}'", text)))
+    }
+}
@@ -0,0 +1,103 @@
+// Links are written in titles as `[[target]]`, the target being
+// `#id` (an item in the same list), `other.todo#id`, `other.todo`,
+// a url or any other file path, bare urls are links too
+
+use std::path::{Path, PathBuf};
+use std::env::{home_dir};
+
+use termion::style;
+
+use dependency::Reference;
+use theme;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Link {
+    Item(Reference),
+    List(String),
+    File(String),
+    Url(String),
+}
+
+fn is_url(text: &str) -> bool {
+    text.starts_with("http://") || text.starts_with("https://") || text.starts_with("mailto:")
+}
+
+// Paths are relative to the directory of the list the link is in
+fn resolve(target: &str, list_path: &str) -> String {
+    let path = if target == "~" || target.starts_with("~/") {
+        home_dir().map_or(PathBuf::from(target), |x| x.join(target.trim_start_matches('~').trim_start_matches('/')))
+    } else {
+        Path::new(list_path).parent().map_or(PathBuf::from(target), |x| x.join(target))
+    };
+    path.to_string_lossy().to_string()
+}
+
+impl Link {
+    pub fn parse(target: &str, list_path: &str) -> Option<Link> {
+        let target = target.trim();
+        if target.is_empty() {
+            None
+        } else if is_url(target) {
+            Some(Link::Url(target.to_owned()))
+        } else if let Some(id) = target.strip_prefix('#') {
+            Some(Link::Item(Reference { list: None, id: id.to_owned() }))
+        } else if let Some(pos) = target.rfind(".todo#") {
+            Some(Link::Item(Reference {
+                list: Some(resolve(&target[..pos + 5], list_path)),
+                id: target[pos + 6..].to_owned(),
+            }))
+        } else if target.ends_with(".todo") {
+            Some(Link::List(resolve(target, list_path)))
+        } else {
+            Some(Link::File(resolve(target, list_path)))
+        }
+    }
+
+    // What it points to, for picking which one to follow
+    pub fn label(&self) -> String {
+        match *self {
+            Link::Item(Reference { list: Some(ref list), ref id }) => format!("{}#{}", list, id),
+            Link::Item(Reference { list: None, ref id }) => format!("#{}", id),
+            Link::List(ref target) | Link::File(ref target) | Link::Url(ref target) => target.clone(),
+        }
+    }
+}
+
+// (start, end, target) byte ranges of every link in a title, including the brackets
+fn link_ranges(title: &str) -> Vec<(usize, usize, &str)> {
+    let mut res = vec![];
+    let mut pos = 0;
+    while pos < title.len() {
+        let rest = &title[pos..];
+        if rest.starts_with("[[") {
+            if let Some(end) = rest.find("]]") {
+                res.push((pos, pos + end + 2, &rest[2..end]));
+                pos += end + 2;
+                continue;
+            }
+        } else if is_url(rest) && title[..pos].chars().last().is_none_or(|x| x.is_whitespace()) {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            res.push((pos, pos + end, &rest[..end]));
+            pos += end;
+            continue;
+        }
+        pos += rest.chars().next().map_or(1, |x| x.len_utf8());
+    }
+    res
+}
+
+pub fn parse_links(title: &str, list_path: &str) -> Vec<Link> {
+    link_ranges(title).iter().filter_map(|x| Link::parse(x.2, list_path)).collect()
+}
+
+pub fn highlight_links(title: &str) -> String {
+    let mut res = String::new();
+    let mut last = 0;
+    for (start, end, _) in link_ranges(title) {
+        res.push_str(&title[last..start]);
+        res.push_str(&format!("{}{}{}", theme::get().link, &title[start..end], style::Reset));
+        last = end;
+    }
+    res.push_str(&title[last..]);
+    res
+}
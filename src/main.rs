@@ -2,10 +2,10 @@
 #![allow(dead_code)]
 
 extern crate clap;
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate toml;
-extern crate dialoguer;
 #[macro_use]
 extern crate indoc;
 extern crate ctrlc;
@@ -13,6 +13,9 @@ extern crate glob;
 #[macro_use]
 extern crate error_chain;
 extern crate termion;
+extern crate chrono;
+extern crate unicode_width;
+extern crate libc;
 
 
 use std::fs::{DirBuilder, canonicalize};
@@ -25,6 +28,20 @@ mod select_helper;
 mod init_communism;
 mod window;
 mod util;
+mod tags;
+mod sort;
+mod config;
+mod archive;
+mod duration;
+mod summary;
+mod time_log;
+mod recurrence;
+mod dependency;
+mod link;
+mod clipboard;
+mod transfer;
+mod theme;
+mod symbols;
 pub use util::*;
 use window::*;
 
@@ -58,6 +75,7 @@ fn main() {
 fn run() -> Result<()> {
     let app = cli::get_app();
     let matches = app.get_matches();
+    symbols::install(matches.is_present("ascii"));
 
     if let ("completions", Some(sub_matches)) = matches.subcommand() {
         let shell = sub_matches.value_of("SHELL").unwrap();
@@ -69,6 +87,74 @@ fn run() -> Result<()> {
         return Ok(());
     }
 
+    if let ("archive", Some(archive_matches)) = matches.subcommand() {
+        let mut list = todo_list::TodoList::load(&PathBuf::from(archive_matches.value_of("FILE").unwrap()))?;
+        let before = match archive_matches.value_of("before") {
+            Some(date) => Some(parse_date(date)?),
+            None => None,
+        };
+        let count = archive::archive_finished(&mut list, before)?;
+        println!("Archived {} item(s) into {}", count, archive::archive_path(&list.path));
+        return Ok(());
+    }
+
+    if let ("tick", Some(tick_matches)) = matches.subcommand() {
+        let config = config::Config::load()?;
+        let mut list = todo_list::TodoList::load(&PathBuf::from(tick_matches.value_of("FILE").unwrap()))?;
+        let path = parse_item_path(tick_matches.value_of("ITEM").unwrap())?;
+        match list.item_at(&path) {
+            Some(item) => {
+                item.set_ticked(true);
+                println!("Ticked off '{}'", item.title);
+            },
+            None => bail!("There is no item {}", tick_matches.value_of("ITEM").unwrap()),
+        }
+        // the next occurrence goes in first so the parents aren't ticked with it inside
+        let spawned = list.spawn_recurrence(&path);
+        if config.auto_tick_parents {
+            list.update_parent_ticks(&path);
+        }
+        if let Some(new_path) = spawned {
+            if let Some(due) = list.item_at(&new_path).and_then(|x| x.due) {
+                println!("Next occurrence is due {}", due);
+            }
+            if config.archive_recurring {
+                // saves the list as well
+                return archive::archive_item(&mut list, &path);
+            }
+        }
+        list.save()?;
+        return Ok(());
+    }
+
+    if let ("summary", Some(summary_matches)) = matches.subcommand() {
+        let list = todo_list::TodoList::load(&PathBuf::from(summary_matches.value_of("FILE").unwrap()))?;
+        summary::print_summary(&list, &config::Config::load()?);
+        return Ok(());
+    }
+
+    if let ("report", Some(report_matches)) = matches.subcommand() {
+        if let ("time", Some(time_matches)) = report_matches.subcommand() {
+            let since = match time_matches.value_of("since") {
+                Some(date) => Some(parse_date(date)?),
+                None => None,
+            };
+            let paths: Vec<PathBuf> = match time_matches.values_of("FILE") {
+                Some(files) => files.map(PathBuf::from).collect(),
+                None => find_lists()?.into_iter().map(|x| x.0).collect(),
+            };
+            let mut lists = vec![];
+            for path in paths.iter() {
+                lists.push(todo_list::TodoList::load(path)?);
+            }
+            summary::print_time_report(&lists, since);
+        } else {
+            println!("{}", report_matches.usage());
+        }
+        return Ok(());
+    }
+
+    theme::install(&config::Config::load()?, theme::Depth::detect(matches.is_present("no-color")));
     let mut view = WindowView::new()?;
     view.set_cursor(false)?;
     ctrlc::set_handler(|| {}).chain_err(|| "Error setting Ctrl-C handler")?; // do explicitly nothing
@@ -0,0 +1,172 @@
+// Rules for items that come back once they are ticked off, written like
+// "every day", "every 2 weeks", "every month on the 1st" or "3 days after completion"
+
+use std::fmt;
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn parse(text: &str) -> Option<Unit> {
+        match text.trim_end_matches('s') {
+            "day" => Some(Unit::Day),
+            "week" => Some(Unit::Week),
+            "month" => Some(Unit::Month),
+            "year" => Some(Unit::Year),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            Unit::Day => "day",
+            Unit::Week => "week",
+            Unit::Month => "month",
+            Unit::Year => "year",
+        }
+    }
+
+    fn add(&self, date: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match *self {
+            Unit::Day => date.checked_add_days(Days::new(count as u64)),
+            Unit::Week => date.checked_add_days(Days::new(7 * count as u64)),
+            Unit::Month => date.checked_add_months(Months::new(count)),
+            Unit::Year => date.checked_add_months(Months::new(12 * count)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recurrence {
+    // on a fixed schedule from the due date
+    Every(u32, Unit),
+    // every N months on a given day of the month
+    MonthlyOn(u32, u32),
+    // from whenever it was last completed
+    AfterCompletion(u32, Unit),
+}
+
+fn parse_day(text: &str) -> Option<u32> {
+    let digits = text.trim_end_matches(|c: char| c.is_alphabetic());
+    match digits.parse::<u32>() {
+        Ok(day) if (1 ..= 31).contains(&day) => Some(day),
+        _ => None,
+    }
+}
+
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11 ..= 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+impl Recurrence {
+    pub fn parse(text: &str) -> Option<Recurrence> {
+        let text = text.trim().to_lowercase();
+        match &text[..] {
+            "daily" => return Some(Recurrence::Every(1, Unit::Day)),
+            "weekly" => return Some(Recurrence::Every(1, Unit::Week)),
+            "monthly" => return Some(Recurrence::Every(1, Unit::Month)),
+            "yearly" => return Some(Recurrence::Every(1, Unit::Year)),
+            _ => {},
+        }
+        let mut words: Vec<&str> = text.split_whitespace().filter(|x| *x != "the").collect();
+        let after_completion = words.ends_with(&["after", "completion"]);
+        if after_completion {
+            words.truncate(words.len() - 2);
+        }
+        if words.first() == Some(&"every") {
+            words.remove(0);
+        } else if !after_completion {
+            return None;
+        }
+
+        // [count] unit [on day]
+        let count = match words.first().and_then(|x| x.parse::<u32>().ok()) {
+            Some(count) if count > 0 => {
+                words.remove(0);
+                count
+            },
+            Some(_) => return None,
+            None => 1,
+        };
+        let unit = Unit::parse(words.first()?)?;
+        match (&words[1..], after_completion) {
+            ([], true) => Some(Recurrence::AfterCompletion(count, unit)),
+            ([], false) => Some(Recurrence::Every(count, unit)),
+            (["on", day], false) if unit == Unit::Month => Some(Recurrence::MonthlyOn(count, parse_day(day)?)),
+            _ => None,
+        }
+    }
+
+    // The first due date after the item was completed on `completed`
+    pub fn next_due(&self, due: Option<NaiveDate>, completed: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Recurrence::AfterCompletion(count, unit) => unit.add(completed, count),
+            Recurrence::Every(count, unit) => {
+                // skip any occurrences that were missed
+                let mut next = unit.add(due.unwrap_or(completed), count)?;
+                while next <= completed {
+                    next = unit.add(next, count)?;
+                }
+                Some(next)
+            },
+            Recurrence::MonthlyOn(count, day) => {
+                let base = due.unwrap_or(completed);
+                let mut month = NaiveDate::from_ymd_opt(base.year(), base.month(), 1)?;
+                loop {
+                    // clamp to the end of short months
+                    let next = (0 .. 4).filter_map(|x| day.checked_sub(x))
+                        .filter_map(|x| NaiveDate::from_ymd_opt(month.year(), month.month(), x)).next()?;
+                    if next > completed && next > base {
+                        return Some(next);
+                    }
+                    month = month.checked_add_months(Months::new(count))?;
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |count: u32, unit: Unit| if count == 1 {
+            unit.name().to_owned()
+        } else {
+            format!("{} {}s", count, unit.name())
+        };
+        match *self {
+            Recurrence::Every(count, unit) => write!(f, "every {}", plural(count, unit)),
+            Recurrence::MonthlyOn(count, day) => write!(f, "every {} on the {}", plural(count, Unit::Month), ordinal(day)),
+            Recurrence::AfterCompletion(1, unit) => write!(f, "1 {} after completion", unit.name()),
+            Recurrence::AfterCompletion(count, unit) => write!(f, "{} after completion", plural(count, unit)),
+        }
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Recurrence, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Recurrence::parse(&text).ok_or_else(|| D::Error::custom(format!("invalid recurrence '{}'", text)))
+    }
+}
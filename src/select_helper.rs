@@ -2,13 +2,15 @@
 
 use termion;
 use termion::event::{Key, Event, MouseButton, MouseEvent};
-use termion::{color, style};
+use termion::style;
 use termion::input::{TermRead};
-use std::io::{Write, Stdin};
+use std::io::{ErrorKind, Write, Stdin};
 use std::iter;
 use std::slice;
 use errors::*;
 use window::WindowView;
+use theme;
+use symbols;
 
 const SCROLL_FACTOR: usize = 1;
 
@@ -17,7 +19,7 @@ fn print_out_selections(view: &mut WindowView, prompt: &String, options: iter::E
     view.write(prompt.as_bytes())?;
     view.write(b"\n\r")?;
     for (i, option) in options {
-        write!(view, "{} {}\n\r", if i == cur {"→"} else {" "}, option)?;
+        write!(view, "{} {}\n\r", if i == cur {symbols::cursor()} else {" "}, option)?;
     }
     view.flush()?;
     Ok(())
@@ -34,6 +36,8 @@ pub fn select(view: &mut WindowView, stdin: &mut Stdin, prompt: String, options:
         let mut report_err = false;
 
         match c {
+            // a resize interrupted the wait for input, it is drawn again below
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => {},
             Ok(Event::Key(key)) => match key {
                 Key::Char('q') | Key::Esc => return Ok(None),
                 Key::Up => cur = if cur > 0 {cur - 1} else {options.len() - 1},
@@ -53,7 +57,7 @@ pub fn select(view: &mut WindowView, stdin: &mut Stdin, prompt: String, options:
         print_out_selections(view, &prompt, options.iter().enumerate(), cur)?;
         if report_err {
             write!(view, "\n\r{red}Unrecognised Key{reset}\n\r",
-                red = color::Fg(color::Red),
+                red = theme::get().error,
                 reset = style::Reset)?;
         }
     }
@@ -0,0 +1,61 @@
+// Stable sorting of todo items, ties keep their manual order
+
+use std::cmp::Ordering;
+
+use todo_list::TodoItem;
+use util;
+
+// Returns the old index of each item in its new position
+// so callers can keep the cursor on the same item.
+pub fn sort_items<F>(items: &mut Vec<TodoItem>, recursive: bool, cmp: &F) -> Vec<usize>
+    where F: Fn(&TodoItem, &TodoItem) -> Ordering {
+    let mut indexed: Vec<(usize, TodoItem)> = items.drain(..).enumerate().collect();
+    indexed.sort_by(|a, b| cmp(&a.1, &b.1));
+    let mut order = vec![];
+    for (i, mut item) in indexed {
+        if recursive {
+            sort_items(&mut item.contents, true, cmp);
+        }
+        order.push(i);
+        items.push(item);
+    }
+    order
+}
+
+// Highest priority first, then unfinished before finished, then soonest due
+pub fn by_priority(a: &TodoItem, b: &TodoItem) -> Ordering {
+    b.priority.cmp(&a.priority)
+        .then(a.ticked_off.cmp(&b.ticked_off))
+        .then_with(|| by_due(a, b))
+}
+
+// Soonest due first, items without a due date go last
+pub fn by_due(a: &TodoItem, b: &TodoItem) -> Ordering {
+    match (a.due, b.due) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Title,
+    Completion,
+    Created,
+    Children,
+    Priority,
+    Due,
+}
+
+pub fn by_key(key: SortKey, a: &TodoItem, b: &TodoItem) -> Ordering {
+    match key {
+        SortKey::Title => util::natural_cmp(&a.title, &b.title),
+        SortKey::Completion => a.ticked_off.cmp(&b.ticked_off),
+        SortKey::Created => a.created.cmp(&b.created),
+        SortKey::Children => a.contents.len().cmp(&b.contents.len()),
+        SortKey::Priority => by_priority(a, b),
+        SortKey::Due => by_due(a, b),
+    }
+}
@@ -0,0 +1,82 @@
+// Plain text output of a list for use outside of the window
+
+use chrono::{DateTime, Local};
+
+use config::Config;
+use duration;
+use symbols;
+use todo_list::{TodoList, TodoItem};
+
+fn estimate_text(remaining: u64, total: u64) -> String {
+    match (remaining, total) {
+        (_, 0) => String::new(),
+        (remaining, total) if remaining == total => format!(" ({})", duration::format_duration(total)),
+        (remaining, total) => format!(" ({}/{} left)", duration::format_duration(remaining), duration::format_duration(total)),
+    }
+}
+
+fn print_item(item: &TodoItem, config: &Config, depth: usize) {
+    let symbol = config.status(item.status_name()).map_or("?".to_owned(), |x| symbols::status(&x.symbol));
+    let progress = if item.contents.is_empty() {
+        String::new()
+    } else {
+        let (done, total) = item.progress();
+        format!(" [{}/{}]", done, total)
+    };
+    let (remaining, total) = item.estimates();
+    // a finished leaf still shows what it was estimated at
+    let remaining = if item.contents.is_empty() {total} else {remaining};
+    let logged = match item.logged_total(None) {
+        0 => String::new(),
+        minutes => format!(" [{} spent]", duration::format_duration(minutes)),
+    };
+    let due = item.due.map_or(String::new(), |x| format!(" due {}{}", x, if item.is_overdue() {" (overdue)"} else {""}));
+    let recur = item.recur.as_ref().map_or(String::new(), |x| format!(" ({})", x));
+    println!("{}[{}] {}{}{}{}{}{}", str::repeat("    ", depth), symbol, item.title, due, recur,
+             progress, estimate_text(remaining, total), logged);
+    for child in item.contents.iter() {
+        print_item(child, config, depth + 1);
+    }
+}
+
+pub fn print_summary(list: &TodoList, config: &Config) {
+    let (done, total) = list.progress();
+    let (done_weight, total_weight) = list.weighted_progress(config.estimate_weighted_progress);
+    let percentage = (100 * done_weight).checked_div(total_weight).unwrap_or(0);
+    let (remaining, estimated) = list.estimates();
+    println!("== {} [{}/{}, {}%]{} ==", list.name, done, total, percentage, estimate_text(remaining, estimated));
+    for item in list.contents.iter() {
+        print_item(item, config, 0);
+    }
+}
+
+// (titles joined with their ancestors, minutes) for every item with logged time
+fn collect_logged(item: &TodoItem, since: Option<DateTime<Local>>, ancestors: &mut Vec<String>, res: &mut Vec<(String, u64)>) {
+    ancestors.push(item.title.clone());
+    let minutes = item.logged(since);
+    if minutes > 0 {
+        res.push((ancestors.join(" > "), minutes));
+    }
+    for child in item.contents.iter() {
+        collect_logged(child, since, ancestors, res);
+    }
+    ancestors.pop();
+}
+
+pub fn print_time_report(lists: &[TodoList], since: Option<DateTime<Local>>) {
+    let mut grand_total = 0;
+    for list in lists.iter() {
+        let mut logged = vec![];
+        for item in list.contents.iter() {
+            collect_logged(item, since, &mut vec![], &mut logged);
+        }
+        let total: u64 = logged.iter().map(|x| x.1).sum();
+        if total == 0 { continue; }
+        println!("{} ({}): {}", list.name, list.path, duration::format_duration(total));
+        for (title, minutes) in logged {
+            println!("    {}: {}", title, duration::format_duration(minutes));
+        }
+        grand_total += total;
+    }
+    println!("Total: {}", duration::format_duration(grand_total));
+}
@@ -0,0 +1,54 @@
+// The non-ASCII characters used when drawing, with plain replacements for
+// terminals (or locales) that can't show them
+
+use std::env;
+use std::sync::OnceLock;
+
+static ASCII: OnceLock<bool> = OnceLock::new();
+
+// Whether the locale says the terminal takes UTF-8
+fn locale_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .filter_map(|x| env::var(x).ok())
+        .find(|x| !x.is_empty())
+        .is_some_and(|x| {
+            let x = x.to_lowercase();
+            x.contains("utf-8") || x.contains("utf8")
+        })
+}
+
+// Should be called once before anything is drawn
+pub fn install(force_ascii: bool) {
+    let _ = ASCII.set(force_ascii || !locale_is_utf8());
+}
+
+pub fn ascii() -> bool {
+    *ASCII.get_or_init(|| !locale_is_utf8())
+}
+
+pub fn cursor() -> &'static str {
+    if ascii() {">"} else {"→"}
+}
+
+// Between the titles of a breadcrumb
+pub fn breadcrumb() -> &'static str {
+    if ascii() {">"} else {"›"}
+}
+
+// Between the panes of a vertical split
+pub fn separator() -> &'static str {
+    if ascii() {"|"} else {"│"}
+}
+
+// Status symbols come from the config so anything unknown becomes '?'
+pub fn status(symbol: &str) -> String {
+    if !ascii() || symbol.is_ascii() {
+        return symbol.to_owned();
+    }
+    match symbol {
+        "✓" | "✔" => "x",
+        "✗" | "✘" => "-",
+        "…" => ".",
+        _ => "?",
+    }.to_owned()
+}
@@ -0,0 +1,85 @@
+// Tags are written inline in titles as `#tag`
+
+use termion::style;
+
+use theme;
+
+fn is_tag_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '-' || ch == '_'
+}
+
+// Returns (start, end) byte ranges of every tag including the '#'
+fn tag_ranges(title: &str) -> Vec<(usize, usize)> {
+    let mut res = vec![];
+    let mut chars = title.char_indices().peekable();
+    let mut prev = ' ';
+    while let Some((i, ch)) = chars.next() {
+        if ch == '#' && prev.is_whitespace() {
+            let mut end = i + 1;
+            while let Some(&(j, next)) = chars.peek() {
+                if !is_tag_char(next) { break; }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            if end > i + 1 {
+                res.push((i, end));
+            }
+            prev = ' ';
+        } else {
+            prev = ch;
+        }
+    }
+    res
+}
+
+pub fn parse_tags(title: &str) -> Vec<String> {
+    tag_ranges(title).iter().map(|&(start, end)| title[start + 1..end].to_lowercase()).collect()
+}
+
+pub fn highlight_tags(title: &str) -> String {
+    let mut res = String::new();
+    let mut last = 0;
+    for (start, end) in tag_ranges(title) {
+        res.push_str(&title[last..start]);
+        res.push_str(&format!("{}{}{}", theme::get().tag, &title[start..end], style::Reset));
+        last = end;
+    }
+    res.push_str(&title[last..]);
+    res
+}
+
+// A tag expression is a set of alternatives separated by '|'
+// each alternative is a set of (optionally negated with '!') tags that all must match
+// i.e. `#work !#done | #urgent`
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+    pub source: String,
+    alternatives: Vec<Vec<(bool, String)>>,
+}
+
+impl TagFilter {
+    pub fn parse(source: &str) -> Option<TagFilter> {
+        let alternatives: Vec<Vec<(bool, String)>> = source.split('|')
+            .map(|alt| alt.split(|c: char| c.is_whitespace() || c == '&')
+                .filter(|x| !x.is_empty())
+                .map(|term| {
+                    let negated = term.starts_with('!');
+                    let name = term.trim_start_matches('!').trim_start_matches('#');
+                    (negated, name.to_lowercase())
+                })
+                .filter(|(_, name)| !name.is_empty())
+                .collect::<Vec<_>>())
+            .filter(|alt| !alt.is_empty())
+            .collect();
+        if alternatives.is_empty() { return None; }
+        Some(TagFilter {
+            source: source.trim().to_owned(),
+            alternatives,
+        })
+    }
+
+    pub fn matches(&self, tags: &[String]) -> bool {
+        self.alternatives.iter().any(|alt| alt.iter()
+            .all(|(negated, name)| tags.contains(name) != *negated))
+    }
+}
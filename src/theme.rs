@@ -0,0 +1,299 @@
+// Colours and styles used when drawing, picked by name from the config
+// i.e. `theme = "light"` or a table under `[themes.<name>]` with entries like
+// `tag = "bold #00afaf"`, `overdue = "red on black"` or `header = "underline 208"`
+
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::OnceLock;
+
+use termion::{color, style};
+
+use config::Config;
+
+static THEME: OnceLock<(Theme, Vec<String>)> = OnceLock::new();
+
+// How many colours the terminal can show
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Depth {
+    None,
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+impl Depth {
+    pub fn detect(no_color: bool) -> Depth {
+        if no_color || env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty()) {
+            return Depth::None;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Depth::TrueColor
+        } else if term.contains("256color") {
+            Depth::Ansi256
+        } else {
+            Depth::Basic
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Color {
+    // 0 - 15
+    Basic(u8),
+    Ansi256(u8),
+    Rgb(u8, u8, u8),
+}
+
+const BASIC_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+// Roughly what the basic colours look like, for picking the closest one
+const BASIC_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0), (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0), (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_level(x: u8) -> usize {
+    (0 .. CUBE_LEVELS.len()).min_by_key(|&i| (CUBE_LEVELS[i] as i32 - x as i32).abs()).unwrap_or(0)
+}
+
+impl Color {
+    fn parse(text: &str) -> Option<Color> {
+        if let Some(hex) = text.strip_prefix('#') {
+            if hex.len() != 6 { return None; }
+            let part = |i: usize| u8::from_str_radix(hex.get(i .. i + 2)?, 16).ok();
+            return Some(Color::Rgb(part(0)?, part(2)?, part(4)?));
+        }
+        if let Ok(value) = text.parse::<u8>() {
+            return Some(Color::Ansi256(value));
+        }
+        let (bright, name) = match text.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None => (0, text),
+        };
+        BASIC_NAMES.iter().position(|x| *x == name).map(|x| Color::Basic(x as u8 + bright))
+    }
+
+    fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            Color::Basic(value) => BASIC_RGB[value as usize % 16],
+            Color::Ansi256(value) if value < 16 => BASIC_RGB[value as usize],
+            Color::Ansi256(value) if value >= 232 => {
+                let grey = 8 + 10 * (value - 232);
+                (grey, grey, grey)
+            },
+            Color::Ansi256(value) => {
+                let value = value - 16;
+                (CUBE_LEVELS[value as usize / 36], CUBE_LEVELS[value as usize / 6 % 6], CUBE_LEVELS[value as usize % 6])
+            },
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    // Falls back to the closest colour the terminal has
+    fn fit(&self, depth: Depth) -> Option<Color> {
+        match (*self, depth) {
+            (_, Depth::None) => None,
+            (Color::Basic(_), _) | (_, Depth::TrueColor) => Some(*self),
+            (Color::Ansi256(_), Depth::Ansi256) => Some(*self),
+            (Color::Rgb(r, g, b), Depth::Ansi256) => {
+                let cube = 16 + 36 * nearest_level(r) + 6 * nearest_level(g) + nearest_level(b);
+                Some(Color::Ansi256(cube as u8))
+            },
+            (color, _) => {
+                let rgb = color.rgb();
+                (0 .. 16).min_by_key(|&i| distance(BASIC_RGB[i], rgb)).map(|x| Color::Basic(x as u8))
+            },
+        }
+    }
+
+    fn escape(&self, background: bool) -> String {
+        match (*self, background) {
+            (Color::Basic(value), false) | (Color::Ansi256(value), false) => color::Fg(color::AnsiValue(value)).to_string(),
+            (Color::Basic(value), true) | (Color::Ansi256(value), true) => color::Bg(color::AnsiValue(value)).to_string(),
+            (Color::Rgb(r, g, b), false) => color::Fg(color::Rgb(r, g, b)).to_string(),
+            (Color::Rgb(r, g, b), true) => color::Bg(color::Rgb(r, g, b)).to_string(),
+        }
+    }
+}
+
+// Words separated by spaces, colours after "on" are for the background
+// an unknown word makes the whole style invalid
+fn parse_style(text: &str, depth: Depth) -> Option<String> {
+    let mut res = String::new();
+    let mut background = false;
+    for word in text.split_whitespace() {
+        match &word.to_lowercase()[..] {
+            "on" => background = true,
+            "bold" => res.push_str(style::Bold.as_ref()),
+            "faint" | "dim" => res.push_str(style::Faint.as_ref()),
+            "italic" => res.push_str(style::Italic.as_ref()),
+            "underline" => res.push_str(style::Underline.as_ref()),
+            "strike" | "strikethrough" => res.push_str(style::CrossedOut.as_ref()),
+            "invert" | "reverse" => res.push_str(style::Invert.as_ref()),
+            "none" | "default" => {},
+            word => {
+                if let Some(color) = Color::parse(word)?.fit(depth) {
+                    res.push_str(&color.escape(background));
+                }
+                background = false;
+            },
+        }
+    }
+    Some(res)
+}
+
+// Every themed part of the screen as the escape sequence that starts it
+#[derive(Clone, Debug, Default)]
+pub struct Theme {
+    pub header: String,
+    pub cursor: String,
+    pub cursor_line: String,
+    pub ticked: String,
+    pub overdue: String,
+    pub due_today: String,
+    pub detail: String,
+    pub tag: String,
+    pub link: String,
+    pub blocked: String,
+    pub timing: String,
+    pub progress_low: String,
+    pub progress_mid: String,
+    pub progress_high: String,
+    pub priority_low: String,
+    pub priority_medium: String,
+    pub priority_high: String,
+    pub priority_critical: String,
+    pub error: String,
+    pub status_line: String,
+    pub current_tab: String,
+}
+
+fn builtin(name: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    let default = vec![
+        ("header", "bold"), ("cursor", ""), ("cursor_line", "invert"), ("ticked", "faint strike"), ("overdue", "red"), ("due_today", "yellow"),
+        ("detail", "faint"), ("tag", "cyan"), ("link", "underline"), ("blocked", "red"), ("timing", "red"),
+        ("progress_low", "red"), ("progress_mid", "yellow"), ("progress_high", "green"),
+        ("priority_low", "blue"), ("priority_medium", "yellow"), ("priority_high", "red"), ("priority_critical", "bold red"),
+        ("error", "red"), ("status_line", "invert"), ("current_tab", "bold invert"),
+    ];
+    let overrides = match name {
+        "default" => vec![],
+        "light" => vec![
+            ("tag", "#005f87"), ("due_today", "#af5f00"), ("progress_mid", "#af5f00"), ("progress_high", "#005f00"),
+            ("priority_medium", "#af5f00"),
+        ],
+        "dark" => vec![
+            ("tag", "#5fd7ff"), ("due_today", "#ffd75f"), ("progress_mid", "#ffd75f"), ("progress_high", "#87d75f"),
+            ("overdue", "bold #ff5f5f"), ("priority_low", "#5f87ff"), ("cursor", "bold"),
+        ],
+        "mono" => vec![
+            ("overdue", "bold"), ("due_today", "underline"), ("tag", "underline"), ("blocked", "bold"), ("timing", "bold"),
+            ("progress_low", ""), ("progress_mid", ""), ("progress_high", ""), ("priority_low", ""), ("priority_medium", ""),
+            ("priority_high", "bold"), ("priority_critical", "bold"), ("error", "bold"),
+        ],
+        _ => return None,
+    };
+    let mut res = default;
+    for (key, value) in overrides {
+        if let Some(entry) = res.iter_mut().find(|x| x.0 == key) {
+            entry.1 = value;
+        }
+    }
+    Some(res)
+}
+
+impl Theme {
+    fn set(&mut self, key: &str, value: String) -> bool {
+        let field = match key {
+            "header" => &mut self.header,
+            "cursor" => &mut self.cursor,
+            "cursor_line" => &mut self.cursor_line,
+            "ticked" => &mut self.ticked,
+            "overdue" => &mut self.overdue,
+            "due_today" => &mut self.due_today,
+            "detail" => &mut self.detail,
+            "tag" => &mut self.tag,
+            "link" => &mut self.link,
+            "blocked" => &mut self.blocked,
+            "timing" => &mut self.timing,
+            "progress_low" => &mut self.progress_low,
+            "progress_mid" => &mut self.progress_mid,
+            "progress_high" => &mut self.progress_high,
+            "priority_low" => &mut self.priority_low,
+            "priority_medium" => &mut self.priority_medium,
+            "priority_high" => &mut self.priority_high,
+            "priority_critical" => &mut self.priority_critical,
+            "error" => &mut self.error,
+            "status_line" => &mut self.status_line,
+            "current_tab" => &mut self.current_tab,
+            _ => return false,
+        };
+        *field = value;
+        true
+    }
+
+    // Custom themes are applied on top of the builtin theme they are `base`d on (or the default one)
+    // returns the theme along with anything in it that wasn't understood
+    pub fn load(config: &Config, depth: Depth) -> (Theme, Vec<String>) {
+        let mut name = config.theme.as_ref().map_or("default", |x| &x[..]);
+        let custom: Option<&BTreeMap<String, String>> = config.themes.get(name);
+        // without colours the builtin themes would lose i.e. overdue dates completely
+        if depth == Depth::None && custom.is_none() {
+            name = "mono";
+        }
+        let base = custom.and_then(|x| x.get("base")).map_or(name, |x| &x[..]);
+        let mut problems = vec![];
+        let mut entries: Vec<(String, String)> = match builtin(base) {
+            Some(entries) => entries.into_iter().map(|(k, v)| (k.to_owned(), v.to_owned())).collect(),
+            None => {
+                if custom.is_none() {
+                    problems.push(format!("unknown theme '{}'", name));
+                } else {
+                    problems.push(format!("unknown base theme '{}'", base));
+                }
+                builtin("default").unwrap_or_default().into_iter().map(|(k, v)| (k.to_owned(), v.to_owned())).collect()
+            },
+        };
+        if let Some(custom) = custom {
+            entries.extend(custom.iter().filter(|x| x.0 != "base").map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        let mut theme = Theme::default();
+        for (key, value) in entries {
+            match parse_style(&value, depth) {
+                Some(style) => if !theme.set(&key, style) {
+                    problems.push(format!("unknown theme entry '{}'", key));
+                },
+                None => problems.push(format!("invalid style '{}' for {}", value, key)),
+            }
+        }
+        (theme, problems)
+    }
+}
+
+fn installed() -> &'static (Theme, Vec<String>) {
+    THEME.get_or_init(|| Theme::load(&Config::default(), Depth::detect(false)))
+}
+
+// Should be called once before anything is drawn
+pub fn install(config: &Config, depth: Depth) {
+    let _ = THEME.set(Theme::load(config, depth));
+}
+
+pub fn get() -> &'static Theme {
+    &installed().0
+}
+
+// What was wrong with the theme in the config
+pub fn problems() -> &'static [String] {
+    &installed().1
+}
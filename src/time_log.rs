@@ -0,0 +1,66 @@
+// Time spent on an item, stored as an ISO 8601 interval "start/end"
+// with a missing end meaning the timer is still running
+
+use std::fmt;
+
+use chrono::{DateTime, Local};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+
+#[derive(Clone, Debug)]
+pub struct TimeEntry {
+    pub start: DateTime<Local>,
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimeEntry {
+    pub fn start_now() -> TimeEntry {
+        TimeEntry {
+            start: Local::now(),
+            end: None,
+        }
+    }
+
+    // Minutes logged from `since` (or all of it)
+    pub fn minutes(&self, since: Option<DateTime<Local>>) -> u64 {
+        let end = self.end.unwrap_or_else(Local::now);
+        let start = match since {
+            Some(since) if since > self.start => since,
+            _ => self.start,
+        };
+        if end > start {(end - start).num_minutes() as u64} else {0}
+    }
+}
+
+impl fmt::Display for TimeEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/", self.start.to_rfc3339())?;
+        if let Some(end) = self.end {
+            write!(f, "{}", end.to_rfc3339())?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for TimeEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TimeEntry, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let parse = |x: &str| DateTime::parse_from_rfc3339(x)
+            .map(|x| x.with_timezone(&Local))
+            .map_err(|_| D::Error::custom(format!("invalid time entry '{}'", text)));
+        let (start, end) = match text.find('/') {
+            Some(pos) => (&text[..pos], &text[pos + 1..]),
+            None => return Err(D::Error::custom(format!("invalid time entry '{}'", text))),
+        };
+        Ok(TimeEntry {
+            start: parse(start)?,
+            end: if end.is_empty() {None} else {Some(parse(end)?)},
+        })
+    }
+}
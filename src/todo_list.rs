@@ -1,5 +1,20 @@
 // A simple but efficient todo list structure
 
+use std::fs::{File, read_to_string};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate};
+use toml;
+
+use errors::*;
+
+use tags;
+use duration;
+use time_log::TimeEntry;
+use recurrence::Recurrence;
+use config::StatusDef;
+
 #[derive(Deserialize)]
 #[derive(Serialize)]
 #[derive(Debug, Clone)]
@@ -18,15 +33,189 @@ impl TodoList {
             contents: vec![],
         }
     }
+
+    pub fn load(path: &Path) -> Result<TodoList> {
+        let mut list: TodoList = toml::from_str(&read_to_string(path)?).chain_err(|| "Failed to load list")?;
+        list.path = path.to_string_lossy().to_string();
+        Ok(list)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut file = File::create(&self.path)?;
+        file.write_all(&toml::to_vec(self)?)?;
+        Ok(())
+    }
+
+    // (finished, total) counted over the leaves of the whole list
+    pub fn progress(&self) -> (usize, usize) {
+        self.contents.iter().map(TodoItem::progress)
+            .fold((0, 0), |(done, total), (x, y)| (done + x, total + y))
+    }
+
+    // (remaining, total) estimated minutes for the whole list
+    pub fn estimates(&self) -> (u64, u64) {
+        self.contents.iter().map(TodoItem::estimates)
+            .fold((0, 0), |(remaining, total), (x, y)| (remaining + x, total + y))
+    }
+
+    // Either weighted by leaves or by estimated time
+    pub fn weighted_progress(&self, by_estimate: bool) -> (u64, u64) {
+        let (remaining, total) = self.estimates();
+        if by_estimate && total > 0 {
+            (total - remaining, total)
+        } else {
+            let (done, total) = self.progress();
+            (done as u64, total as u64)
+        }
+    }
+
+    // returns true if a timer was running
+    pub fn stop_timers(&mut self) -> bool {
+        let mut stopped = false;
+        for item in self.contents.iter_mut() {
+            stopped |= item.stop_timers();
+        }
+        stopped
+    }
+
+    pub fn running_timer(&self) -> Option<&TodoItem> {
+        self.contents.iter().filter_map(TodoItem::running_timer).next()
+    }
+
+    // Ticks/unticks the parents of `path` to match their children
+    pub fn update_parent_ticks(&mut self, path: &[usize]) {
+        for depth in (1 .. path.len()).rev() {
+            match self.item_at(&path[..depth]) {
+                Some(parent) => if !parent.sync_tick_with_children() { break; },
+                None => break,
+            }
+        }
+    }
+
+    // If the (ticked) item at `path` recurs then its next occurrence is placed after it
+    // returns the path to the new item
+    pub fn spawn_recurrence(&mut self, path: &[usize]) -> Option<Vec<usize>> {
+        let next = self.item_at(path)?.next_occurrence()?;
+        let (index, parent_path) = path.split_last()?;
+        let siblings = if parent_path.is_empty() {
+            &mut self.contents
+        } else {
+            &mut self.item_at(parent_path)?.contents
+        };
+        siblings.insert(index + 1, next);
+        let mut res = path.to_vec();
+        *res.last_mut()? += 1;
+        Some(res)
+    }
+
+    pub fn item_at(&mut self, path: &[usize]) -> Option<&mut TodoItem> {
+        let (first, rest) = path.split_first()?;
+        let mut item = self.contents.get_mut(*first)?;
+        for i in rest {
+            item = item.contents.get_mut(*i)?;
+        }
+        Some(item)
+    }
+
+    // The children of the item at `path`, the top level items for an empty path
+    pub fn contents_at(&mut self, path: &[usize]) -> Option<&mut Vec<TodoItem>> {
+        if path.is_empty() {
+            Some(&mut self.contents)
+        } else {
+            self.item_at(path).map(|x| &mut x.contents)
+        }
+    }
+
+    // Removes the item at `path` (and everything under it)
+    pub fn take_at(&mut self, path: &[usize]) -> Option<TodoItem> {
+        let (&index, parent) = path.split_last()?;
+        let items = self.contents_at(parent)?;
+        if index < items.len() {Some(items.remove(index))} else {None}
+    }
+
+    // every tag used in the list, sorted and without duplicates
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut res = vec![];
+        for item in self.contents.iter() {
+            item.collect_tags(&mut res);
+        }
+        res.sort();
+        res.dedup();
+        res
+    }
+}
+
+#[derive(Deserialize)]
+#[derive(Serialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
 }
 
+impl Priority {
+    pub fn is_none(&self) -> bool {
+        *self == Priority::None
+    }
+
+    pub fn raise(self) -> Priority {
+        match self {
+            Priority::None => Priority::Low,
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            _ => Priority::Critical,
+        }
+    }
+
+    pub fn lower(self) -> Priority {
+        match self {
+            Priority::Critical => Priority::High,
+            Priority::High => Priority::Medium,
+            Priority::Medium => Priority::Low,
+            _ => Priority::None,
+        }
+    }
+}
+
+// Note: any new fields that aren't tables need to go before `contents`
+//       else toml will refuse to serialize them.
 #[derive(Deserialize)]
 #[derive(Serialize)]
 #[derive(Clone)]
 #[derive(Debug)]
 pub struct TodoItem {
+    // Still the source of truth for 'is this finished' so older files keep working
     pub ticked_off: bool,
+    // Only given out once something needs to refer to the item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // References (see `dependency::Reference`) to items that need to be finished first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    // Any status other than plain todo/done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
     pub title: String,
+    #[serde(default, skip_serializing_if = "Priority::is_none")]
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recur: Option<Recurrence>,
+    // in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "duration::optional")]
+    pub estimate: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_log: Vec<TimeEntry>,
     pub contents: Vec<TodoItem>,
     // as well as a link
 }
@@ -35,8 +224,197 @@ impl TodoItem {
     pub fn create(title: String) -> TodoItem {
         TodoItem {
             ticked_off: false,
+            id: None,
+            depends_on: vec![],
+            status: None,
             title: title,
+            priority: Priority::None,
+            created: Some(Local::now()),
+            completed: None,
+            due: None,
+            recur: None,
+            estimate: None,
+            time_log: vec![],
             contents: vec![],
         }
     }
+
+    pub fn status_name(&self) -> &str {
+        match self.status {
+            Some(ref status) => status,
+            None => if self.ticked_off {"done"} else {"todo"},
+        }
+    }
+
+    fn set_done(&mut self, done: bool) {
+        if done && !self.ticked_off {
+            self.completed = Some(Local::now());
+        } else if !done {
+            self.completed = None;
+        }
+        self.ticked_off = done;
+    }
+
+    pub fn set_status(&mut self, status: &StatusDef) {
+        self.set_done(status.done);
+        self.status = match &status.name[..] {
+            "todo" | "done" => None,
+            name => Some(name.to_owned()),
+        };
+    }
+
+    // returns true if anything changed
+    pub fn set_ticked(&mut self, ticked: bool) -> bool {
+        if self.ticked_off == ticked && self.status.is_none() { return false; }
+        self.set_done(ticked);
+        self.status = None;
+        true
+    }
+
+    pub fn toggle(&mut self) {
+        let ticked = !self.ticked_off;
+        self.set_ticked(ticked);
+    }
+
+    // (finished, total) counted over leaves, a finished parent finishes all its leaves
+    pub fn progress(&self) -> (usize, usize) {
+        if self.contents.is_empty() {
+            return (if self.ticked_off {1} else {0}, 1);
+        }
+        let (done, total) = self.contents.iter().map(TodoItem::progress)
+            .fold((0, 0), |(done, total), (x, y)| (done + x, total + y));
+        (if self.ticked_off {total} else {done}, total)
+    }
+
+    // (remaining, total) estimated minutes, children's estimates replace the parent's own
+    pub fn estimates(&self) -> (u64, u64) {
+        let (remaining, total) = self.contents.iter().map(TodoItem::estimates)
+            .fold((0, 0), |(remaining, total), (x, y)| (remaining + x, total + y));
+        let (remaining, total) = if total == 0 {
+            let own = self.estimate.unwrap_or(0);
+            (own, own)
+        } else {
+            (remaining, total)
+        };
+        (if self.ticked_off {0} else {remaining}, total)
+    }
+
+    pub fn weighted_progress(&self, by_estimate: bool) -> (u64, u64) {
+        let (remaining, total) = self.estimates();
+        if by_estimate && total > 0 {
+            (total - remaining, total)
+        } else {
+            let (done, total) = self.progress();
+            (done as u64, total as u64)
+        }
+    }
+
+    // A fresh copy of a finished recurring item, the rule moves over
+    // to the new item so ticking the old one again won't create another.
+    pub fn next_occurrence(&mut self) -> Option<TodoItem> {
+        if !self.ticked_off { return None; }
+        let completed = self.completed.unwrap_or_else(Local::now).date_naive();
+        let due = self.recur.as_ref()?.next_due(self.due, completed)?;
+        let mut next = self.clone();
+        next.reset();
+        next.due = Some(due);
+        self.recur = None;
+        Some(next)
+    }
+
+    // Unfinished with no history
+    fn reset(&mut self) {
+        self.ticked_off = false;
+        self.id = None;
+        self.status = None;
+        self.created = Some(Local::now());
+        self.completed = None;
+        self.time_log.clear();
+        for child in self.contents.iter_mut() {
+            child.reset();
+        }
+    }
+
+    // Ticked along with everything under it
+    pub fn is_complete(&self) -> bool {
+        self.ticked_off && self.contents.iter().all(|x| x.is_complete())
+    }
+
+    pub fn is_overdue(&self) -> bool {
+        !self.ticked_off && self.due.is_some_and(|x| x < Local::now().date_naive())
+    }
+
+    pub fn is_timing(&self) -> bool {
+        self.time_log.last().is_some_and(|x| x.end.is_none())
+    }
+
+    pub fn start_timer(&mut self) {
+        if !self.is_timing() {
+            self.time_log.push(TimeEntry::start_now());
+        }
+    }
+
+    // Stops any timer on this item or under it, returns true if one was running
+    pub fn stop_timers(&mut self) -> bool {
+        let mut stopped = false;
+        if let Some(entry) = self.time_log.last_mut().filter(|x| x.end.is_none()) {
+            entry.end = Some(Local::now());
+            stopped = true;
+        }
+        for child in self.contents.iter_mut() {
+            stopped |= child.stop_timers();
+        }
+        stopped
+    }
+
+    pub fn running_timer(&self) -> Option<&TodoItem> {
+        if self.is_timing() {
+            Some(self)
+        } else {
+            self.contents.iter().filter_map(TodoItem::running_timer).next()
+        }
+    }
+
+    // Minutes logged on just this item
+    pub fn logged(&self, since: Option<DateTime<Local>>) -> u64 {
+        self.time_log.iter().map(|x| x.minutes(since)).sum()
+    }
+
+    pub fn logged_total(&self, since: Option<DateTime<Local>>) -> u64 {
+        self.logged(since) + self.contents.iter().map(|x| x.logged_total(since)).sum::<u64>()
+    }
+
+    // Ticks a parent once all children are ticked and unticks it once any aren't
+    // returns true if the tick changed
+    pub fn sync_tick_with_children(&mut self) -> bool {
+        if self.contents.is_empty() { return false; }
+        let all_done = self.contents.iter().all(|x| x.ticked_off);
+        if all_done != self.ticked_off {
+            self.toggle();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Sets the tick on this item and everything under it
+    // returns how many items changed
+    pub fn set_ticked_recursive(&mut self, ticked: bool) -> usize {
+        let mut changed = if self.set_ticked(ticked) {1} else {0};
+        for child in self.contents.iter_mut() {
+            changed += child.set_ticked_recursive(ticked);
+        }
+        changed
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        tags::parse_tags(&self.title)
+    }
+
+    fn collect_tags(&self, res: &mut Vec<String>) {
+        res.extend(self.tags());
+        for child in self.contents.iter() {
+            child.collect_tags(res);
+        }
+    }
 }
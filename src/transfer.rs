@@ -0,0 +1,84 @@
+// Moving an item (and everything under it) from one list into another
+
+use errors::*;
+use todo_list::{TodoList, TodoItem};
+use dependency::{self, Reference};
+use util;
+
+fn map_dependencies<F: Fn(Reference) -> Reference>(items: &mut [TodoItem], f: &F) {
+    for item in items.iter_mut() {
+        for dependency in item.depends_on.iter_mut() {
+            *dependency = f(Reference::parse(dependency)).to_string();
+        }
+        map_dependencies(&mut item.contents, f);
+    }
+}
+
+// Dependencies written as just an id only work within a list so those
+// crossing between the moved item and the rest of either list are rewritten
+fn relink(list: &mut TodoList, item: &mut TodoItem, dest: &mut TodoList) {
+    let mut moved = vec![];
+    dependency::collect_ids(::std::slice::from_ref(item), &mut moved);
+    let source_path = util::canonical_path(&list.path);
+    let dest_path = util::canonical_path(&dest.path);
+
+    map_dependencies(::std::slice::from_mut(item), &|x| match x.list {
+        None if !moved.contains(&x.id) => Reference { list: Some(source_path.clone()), id: x.id },
+        Some(ref path) if util::same_path(path, &dest.path) => Reference { list: None, id: x.id.clone() },
+        _ => x,
+    });
+    map_dependencies(&mut list.contents, &|x| match x.list {
+        None if moved.contains(&x.id) => Reference { list: Some(dest_path.clone()), id: x.id },
+        _ => x,
+    });
+    map_dependencies(&mut dest.contents, &|x| match x.list {
+        Some(ref path) if moved.contains(&x.id) && util::same_path(path, &list.path) => Reference { list: None, id: x.id.clone() },
+        _ => x,
+    });
+}
+
+// Every item as (path, title indented by depth) for picking a new parent
+pub fn item_paths(items: &[TodoItem]) -> Vec<(Vec<usize>, String)> {
+    let mut res = vec![];
+    for (i, item) in items.iter().enumerate() {
+        res.push((vec![i], item.title.clone()));
+        for (mut path, title) in item_paths(&item.contents) {
+            path.insert(0, i);
+            res.push((path, format!("    {}", title)));
+        }
+    }
+    res
+}
+
+// Moves the item at `path` to the end of the children of `parent` in `dest` and saves both lists,
+// the destination goes first and is put back if the source can't be saved so the item is never lost or doubled up
+pub fn move_item(list: &mut TodoList, path: &[usize], dest: &mut TodoList, parent: &[usize]) -> Result<()> {
+    let original_list = list.contents.clone();
+    let original_dest = dest.contents.clone();
+    // checked before anything is changed (relinking rewrites both lists)
+    if dest.contents_at(parent).is_none() { bail!("Invalid destination"); }
+    let mut item = match list.take_at(path) {
+        Some(item) => item,
+        None => bail!("Invalid item"),
+    };
+    relink(list, &mut item, dest);
+    if let Some(items) = dest.contents_at(parent) {
+        items.push(item);
+    }
+
+    if let Err(err) = dest.save() {
+        list.contents = original_list;
+        dest.contents = original_dest;
+        // a failed save can leave the file half written
+        let _ = dest.save();
+        return Err(err).chain_err(|| format!("Failed to save '{}'", dest.path));
+    }
+    if let Err(err) = list.save() {
+        list.contents = original_list;
+        dest.contents = original_dest;
+        dest.save().chain_err(|| format!("Failed to roll back '{}'", dest.path))?;
+        let _ = list.save();
+        return Err(err).chain_err(|| format!("Failed to save '{}'", list.path));
+    }
+    Ok(())
+}
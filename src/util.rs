@@ -1,6 +1,11 @@
+use std::cmp::Ordering;
+use std::fs::canonicalize;
 use std::path::{PathBuf};
 use std::env::{home_dir};
 
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use unicode_width::UnicodeWidthChar;
+
 use errors::*;
 
 pub fn get_file_path() -> Result<PathBuf> {
@@ -13,4 +18,140 @@ pub fn get_file_path() -> Result<PathBuf> {
             bail!("Invalid Path")
         },
     }
-}
\ No newline at end of file
+}
+
+// YYYY-MM-DD, 'today' or 'tomorrow'
+pub fn parse_day(date: &str) -> Result<NaiveDate> {
+    let today = Local::now().date_naive();
+    match &date.trim().to_lowercase()[..] {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today.succ_opt().unwrap_or(today)),
+        date => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .chain_err(|| format!("Invalid date '{}' (expected YYYY-MM-DD)", date)),
+    }
+}
+
+// Falls back to the path as given if it doesn't exist
+pub fn canonical_path(path: &str) -> String {
+    canonicalize(path).map(|x| x.to_string_lossy().to_string()).unwrap_or_else(|_| path.to_owned())
+}
+
+pub fn same_path(a: &str, b: &str) -> bool {
+    a == b || match (canonicalize(a), canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Dates are given as YYYY-MM-DD and mean the start of that day
+pub fn parse_date(date: &str) -> Result<DateTime<Local>> {
+    let date = parse_day(date)?;
+    match date.and_hms_opt(0, 0, 0).and_then(|x| Local.from_local_datetime(&x).earliest()) {
+        Some(date) => Ok(date),
+        None => bail!("Invalid date '{}'", date),
+    }
+}
+
+// "1.2.3" (1-indexed like 'g') to the path [0, 1, 2]
+pub fn parse_item_path(text: &str) -> Result<Vec<usize>> {
+    let mut res = vec![];
+    for part in text.trim().split('.') {
+        match part.parse::<usize>() {
+            Ok(num) if num > 0 => res.push(num - 1),
+            _ => bail!("Invalid item '{}' (expected something like 1.2.3)", text),
+        }
+    }
+    Ok(res)
+}
+
+// Compares strings so that "item 2" comes before "item 10"
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().cloned(), b.peek().cloned()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x_num = String::new();
+                while let Some(ch) = a.peek().cloned().filter(char::is_ascii_digit) {
+                    x_num.push(ch);
+                    a.next();
+                }
+                let mut y_num = String::new();
+                while let Some(ch) = b.peek().cloned().filter(char::is_ascii_digit) {
+                    y_num.push(ch);
+                    b.next();
+                }
+                // compare by length first (without leading zeros) to avoid overflowing
+                let x_trim = x_num.trim_start_matches('0');
+                let y_trim = y_num.trim_start_matches('0');
+                let ord = x_trim.len().cmp(&y_trim.len()).then_with(|| x_trim.cmp(y_trim));
+                if ord != Ordering::Equal { return ord; }
+            },
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal { return ord; }
+                a.next();
+                b.next();
+            },
+        }
+    }
+}
+
+// Splits off the escape sequence (CSI or OSC) at the start of `text`, if there is one
+fn escape_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    if chars.next()?.1 != '\x1b' { return None; }
+    match chars.next()?.1 {
+        '[' => chars.find(|x| ('@' ..= '~').contains(&x.1)).map(|x| x.0 + 1),
+        ']' => chars.find(|x| x.1 == '\x07').map(|x| x.0 + 1),
+        _ => Some(1),
+    }
+}
+
+// The number of columns shown, ignoring escape sequences (wide characters take two)
+pub fn visible_len(text: &str) -> usize {
+    let mut len = 0;
+    let mut pos = 0;
+    while pos < text.len() {
+        match escape_len(&text[pos..]) {
+            Some(skip) => pos += skip,
+            None => {
+                let ch = text[pos..].chars().next().unwrap_or(' ');
+                len += ch.width().unwrap_or(0);
+                pos += ch.len_utf8();
+            },
+        }
+    }
+    len
+}
+
+// Cuts `text` down to `width` columns, keeping every escape sequence
+pub fn truncate_visible(text: &str, width: usize) -> String {
+    let mut res = String::new();
+    let mut len = 0;
+    let mut pos = 0;
+    while pos < text.len() {
+        match escape_len(&text[pos..]) {
+            Some(skip) => {
+                res.push_str(&text[pos..pos + skip]);
+                pos += skip;
+            },
+            None => {
+                let ch = text[pos..].chars().next().unwrap_or(' ');
+                let ch_width = ch.width().unwrap_or(0);
+                // a wide character that doesn't fit is left out completely
+                if len + ch_width <= width {
+                    res.push(ch);
+                    len += ch_width;
+                } else {
+                    len = width;
+                }
+                pos += ch.len_utf8();
+            },
+        }
+    }
+    res
+}
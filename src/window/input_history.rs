@@ -0,0 +1,93 @@
+// Per prompt history for `WindowView::get_user_input_buf`
+// persisted between sessions in `~/_todo_lists/history.toml`
+
+use std::collections::BTreeMap;
+use std::fs::{File, read_to_string};
+use std::io::Write;
+use std::path::PathBuf;
+
+use toml;
+
+use errors::*;
+use util;
+
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InputKind {
+    Item,
+    Edit,
+    Goto,
+    Path,
+    Name,
+    Filter,
+    Estimate,
+    Date,
+    Recurrence,
+}
+
+impl InputKind {
+    fn key(&self) -> &'static str {
+        match *self {
+            InputKind::Item => "item",
+            InputKind::Edit => "edit",
+            InputKind::Goto => "goto",
+            InputKind::Path => "path",
+            InputKind::Name => "name",
+            InputKind::Filter => "filter",
+            InputKind::Estimate => "estimate",
+            InputKind::Date => "date",
+            InputKind::Recurrence => "recurrence",
+        }
+    }
+}
+
+pub struct InputHistory {
+    entries: BTreeMap<String, Vec<String>>,
+}
+
+impl InputHistory {
+    fn history_path() -> Result<PathBuf> {
+        let mut path = util::get_file_path()?;
+        path.push("history.toml");
+        Ok(path)
+    }
+
+    // A missing or broken history file just means we start fresh
+    pub fn load() -> Self {
+        let entries = Self::history_path().ok()
+            .and_then(|path| read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default();
+        InputHistory { entries }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut file = File::create(Self::history_path()?)?;
+        file.write_all(&toml::to_vec(&self.entries)?)?;
+        Ok(())
+    }
+
+    // oldest first
+    pub fn entries(&self, kind: InputKind) -> &[String] {
+        self.entries.get(kind.key()).map_or(&[], |x| &x[..])
+    }
+
+    pub fn push(&mut self, kind: InputKind, entry: &str) {
+        if entry.trim().is_empty() { return; }
+        let list = self.entries.entry(kind.key().to_owned()).or_default();
+        list.retain(|x| x != entry);
+        list.push(entry.to_owned());
+        if list.len() > MAX_ENTRIES {
+            let extra = list.len() - MAX_ENTRIES;
+            list.drain(..extra);
+        }
+    }
+
+    // Searches backwards from (but not including) `before` for an entry containing `query`
+    pub fn search(&self, kind: InputKind, query: &str, before: usize) -> Option<usize> {
+        let list = self.entries(kind);
+        let before = before.min(list.len());
+        list[..before].iter().rposition(|x| x.contains(query))
+    }
+}
@@ -1,45 +1,242 @@
+use std::cmp::Ordering;
 use std::fs::{remove_file};
+use std::io::{self, ErrorKind, stdin};
+use std::env;
+use std::process::{Command, Stdio};
 
-use dialoguer;
+use termion::style;
 use termion::event::{Key};
 use termion::input::{TermRead};
 
 use todo_list;
+use tags;
+use sort;
+use select_helper;
+use archive;
+use util;
+use duration;
+use recurrence::Recurrence;
+use dependency::{self, Reference};
+use link::{self, Link};
+use clipboard;
+use transfer;
 use window::*;
 
+// Where 'v', 'V' and 'ctrl + v' put the pasted items
+enum Paste {
+    Before,
+    After,
+    Child,
+}
+
+// Waits for a single key, a resize interrupting the read isn't one
+fn next_key() -> Option<io::Result<Key>> {
+    stdin().keys().find(|x| !matches!(x, Err(ref e) if e.kind() == ErrorKind::Interrupted))
+}
+
 impl Window {
+    fn sort_level<F>(&mut self, recursive: bool, cmp: &F) -> Result<()>
+        where F: Fn(&todo_list::TodoItem, &todo_list::TodoItem) -> Ordering {
+        let cur_list = self.state.cur_parent_list();
+        if !cur_list.is_empty() {
+            self.state.snapshot();
+            let order = sort::sort_items(cur_list, recursive, cmp);
+            // keep the cursor on the same item
+            let last = self.state.last_cur()?;
+            *last = order.iter().position(|&i| i == *last).unwrap_or(0);
+            self.state.set_changes(true);
+            self.dirty_window = true;
+        }
+        Ok(())
+    }
+
+    // Called once the current item is ticked, recurring items create their next occurrence
+    // (before the parents are ticked so they aren't left ticked with it inside)
+    fn completed_cur(&mut self) -> Result<()> {
+        let path = self.state.cur_path();
+        let list = self.state.cur_loaded_list();
+        let spawned = list.spawn_recurrence(&path);
+        self.state.update_parent_ticks();
+        if let Some(new_path) = spawned {
+            let due = list.item_at(&new_path).and_then(|x| x.due).map_or(String::new(), |x| x.to_string());
+            let mut message = format!("Next occurrence is due {}", due);
+            if self.state.config.archive_recurring {
+                // saves the list as well (with any other unsaved changes)
+                archive::archive_item(list, &path)?;
+                self.state.set_changes(false);
+                self.state.set_destructive_changes(false);
+                self.state.clear_history();
+                message += ", the finished one was archived and the list saved";
+            }
+            self.state.message = Some(message);
+            self.dirty_window = true;
+        }
+        Ok(())
+    }
+
+    // Switches to the tab for the list at `path` (opening it if needed)
+    fn go_to_list(&mut self, path: &str) -> Result<()> {
+        if util::same_path(path, &self.state.cur_loaded_list().path) { return Ok(()); }
+        self.state.open_list(path)?;
+        self.state.check_dependencies();
+        self.dirty_window = true;
+        Ok(())
+    }
+
+    fn go_to(&mut self, jump: &Jump) -> Result<()> {
+        self.go_to_list(&jump.list)?;
+        self.state.set_cur(&jump.cur);
+        self.state.clamp_cur();
+        self.dirty_window = true;
+        Ok(())
+    }
+
+    // Goes to an item in the current list (or the one at `list`), remembering where we were
+    fn go_to_item(&mut self, list: Option<&str>, id: &str) -> Result<()> {
+        self.state.push_jump();
+        if let Some(list) = list {
+            self.go_to_list(list)?;
+        }
+        match dependency::find_path(&self.state.cur_loaded_list().contents, id) {
+            Some(path) => self.state.set_cur(&path),
+            None => self.state.message = Some(format!("No item with the id '{}'", id)),
+        }
+        self.dirty_window = true;
+        Ok(())
+    }
+
+    fn follow_link(&mut self, link: Link) -> Result<()> {
+        match link {
+            Link::Item(Reference { list, id }) => self.go_to_item(list.as_ref().map(|x| &x[..]), &id)?,
+            Link::List(path) => {
+                self.state.push_jump();
+                self.go_to_list(&path)?;
+            },
+            Link::File(path) => {
+                // i.e. `emacsclient -t` has arguments of its own before the file
+                let editor = env::var("EDITOR").unwrap_or_default();
+                let mut words = editor.split_whitespace();
+                match words.next() {
+                    Some(program) => {
+                        self.view.run_external(Command::new(program).args(words).arg(&path))?;
+                        self.dirty_window = true;
+                    },
+                    None => Self::open_externally(&path)?,
+                }
+            },
+            Link::Url(url) => Self::open_externally(&url)?,
+        }
+        Ok(())
+    }
+
+    // With whatever the desktop uses for the file or url
+    fn open_externally(target: &str) -> Result<()> {
+        let opener = if cfg!(target_os = "macos") {"open"} else {"xdg-open"};
+        Command::new(opener).arg(target)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .chain_err(|| format!("Failed to run {}", opener))?;
+        Ok(())
+    }
+
+    // Puts yanked/cut items into the chosen register (and maybe the system clipboard)
+    fn store_register(&mut self, items: Vec<todo_list::TodoItem>, verb: &str) -> Result<()> {
+        let name = self.state.take_register();
+        if name == '+' || self.state.config.osc52_clipboard {
+            write!(self.view, "{}", clipboard::osc52(&clipboard::to_text(&items)))?;
+        }
+        self.state.message = Some(format!("{} {} item(s) into register \"{}", verb, items.len(), name));
+        self.state.set_register(name, items);
+        Ok(())
+    }
+
+    fn paste(&mut self, place: Paste) -> Result<()> {
+        let name = self.state.take_register();
+        let mut items = match self.state.register(name) {
+            Some(items) if !items.is_empty() => items,
+            _ => {
+                self.state.message = Some(format!("Register \"{} is empty", name));
+                return Ok(());
+            },
+        };
+        self.state.snapshot();
+        // pasting a copy shouldn't make two items with the same id
+        for item in items.iter_mut() {
+            dependency::clear_taken_ids(&self.state.cur_loaded_list().contents, item);
+        }
+        let count = items.len();
+        let cur_list = self.state.cur_parent_list();
+        match place {
+            Paste::Child if !cur_list.is_empty() => {
+                let parent = &mut self.state.cur_item()?.contents;
+                let at = parent.len();
+                parent.extend(items);
+                self.state.push_cur(at);
+            },
+            Paste::After if !cur_list.is_empty() => {
+                let last = self.state.last_cur()?;
+                let at = *last + 1;
+                cur_list.splice(at .. at, items);
+                *last = at;
+            },
+            _ => {
+                let at = *self.state.last_cur()?;
+                cur_list.splice(at .. at, items);
+            },
+        }
+        self.state.update_parent_ticks();
+        self.state.message = Some(format!("Pasted {} item(s) from register \"{}", count, name));
+        self.state.set_changes(true);
+        self.dirty_window = true;
+        Ok(())
+    }
+
     pub fn handle_key_event(&mut self, event: Key) -> Result<bool> {
         let cur_list = self.state.cur_parent_list();
+        self.view.tag_source = Some(self.state.cur_shared_list());
+        // a count before a movement repeats it
+        match event {
+            Key::Char(digit @ '0' ..= '9') if digit != '0' || self.state.pending_count.is_some() => {
+                let count = self.state.pending_count.unwrap_or(0);
+                self.state.pending_count = Some(count.saturating_mul(10).saturating_add(digit as usize - '0' as usize));
+                return Ok(false);
+            },
+            _ => {},
+        }
+        let count = self.state.pending_count.take().unwrap_or(1);
         match event {
             Key::Down | Key::Char('j') => {
-                self.state.move_cur_down(1)?;
+                self.state.move_cur_down(count)?;
                 self.dirty_window = true;
             },
             Key::Up | Key::Char('k') => {
-                self.state.move_cur_up(1)?;
+                self.state.move_cur_up(count)?;
                 self.dirty_window = true;
             },
             Key::Left | Key::Char('h') => {
-                if self.state.cur_depth() > 1 {
+                if self.state.can_pop_cur() {
                     self.state.pop_cur()?;
                     self.dirty_window = true;
                 }
             },
             Key::Right | Key::Char('l') if cur_list.len() > 0 => {
                 let cur_item = self.state.cur_item()?;
-                if cur_item.contents.len() > 0 {
-                    self.state.push_cur(0);
+                if let Some(first) = self.state.first_visible(&cur_item.contents) {
+                    self.state.push_cur(first);
                     self.dirty_window = true;
                 }
             },
             Key::Char('\t') if cur_list.len() > 0 => {
                 let cur_item = self.state.cur_item()?;
-                if let Some(new_title) = self.view.get_user_input("New Item", false)? {
+                if let Some(new_title) = self.view.get_user_input("New Item", InputKind::Item)? {
+                    self.state.snapshot();
                     let new_item = todo_list::TodoItem::create(new_title);
                     let i = cur_item.contents.len();
                     cur_item.contents.insert(i, new_item);
                     self.state.push_cur(i);
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                     self.dirty_window = true;
                 }
             },
@@ -47,9 +244,10 @@ impl Window {
                 let cur_item = self.state.cur_item()?;
                 // edit from beginning
                 if cur_list.len() > 0 {
-                    if let Some(new_title) = self.view.get_user_input_buf("Edit Item", &cur_item.title, Some(0), false)? {
+                    if let Some(new_title) = self.view.get_user_input_buf("Edit Item", &cur_item.title, Some(0), InputKind::Edit)? {
+                        self.state.snapshot();
                         cur_item.title = new_title;
-                        self.state.changes = true;
+                        self.state.set_changes(true);
                     }
                 }
             }
@@ -57,9 +255,10 @@ impl Window {
                 let cur_item = self.state.cur_item()?;
                 // edit from end
                 if cur_list.len() > 0 {
-                    if let Some(new_title) = self.view.get_user_input_buf("Edit Item", &cur_item.title, None, false)? {
+                    if let Some(new_title) = self.view.get_user_input_buf("Edit Item", &cur_item.title, None, InputKind::Edit)? {
+                        self.state.snapshot();
                         cur_item.title = new_title;
-                        self.state.changes = true;
+                        self.state.set_changes(true);
                     }
                 }
             },
@@ -67,9 +266,10 @@ impl Window {
                 let cur_item = self.state.cur_item()?;
                 // wipe line and edit
                 if cur_list.len() > 0 {
-                    if let Some(new_title) = self.view.get_user_input("Edit Item", false)? {
+                    if let Some(new_title) = self.view.get_user_input("Edit Item", InputKind::Edit)? {
+                        self.state.snapshot();
                         cur_item.title = new_title;
-                        self.state.changes = true;
+                        self.state.set_changes(true);
                     }
                 }
             },
@@ -77,38 +277,293 @@ impl Window {
                 let cur_item = self.state.cur_item()?;
                 // Toggle
                 if cur_list.len() > 0 {
-                    cur_item.ticked_off = !cur_item.ticked_off;
-                    self.state.changes = true;
+                    self.state.snapshot();
+                    cur_item.toggle();
+                    self.state.set_changes(true);
+                    self.dirty_window = true;
+                    if cur_item.ticked_off {
+                        let blocked_by = self.state.blocked_by(cur_item);
+                        if !blocked_by.is_empty() {
+                            self.state.message = Some(format!("Warning: this is still blocked by '{}'", blocked_by.join("', '")));
+                        }
+                        self.completed_cur()?;
+                    } else {
+                        self.state.update_parent_ticks();
+                    }
+                }
+            },
+            Key::Char('x') | Key::Char('X') if !cur_list.is_empty() => {
+                // cycle through statuses
+                let statuses = self.state.config.statuses();
+                let cur_item = self.state.cur_item()?;
+                let len = statuses.len();
+                let pos = statuses.iter().position(|x| x.name == cur_item.status_name()).unwrap_or(0);
+                let next = if event == Key::Char('x') {(pos + 1) % len} else {(pos + len - 1) % len};
+                self.state.snapshot();
+                cur_item.set_status(&statuses[next]);
+                self.state.set_changes(true);
+                self.dirty_window = true;
+                if cur_item.ticked_off {
+                    self.completed_cur()?;
+                } else {
+                    self.state.update_parent_ticks();
+                }
+            },
+            Key::Char('T') if !cur_list.is_empty() => {
+                // (un)tick the item and everything under it
+                let cur_item = self.state.cur_item()?;
+                let ticked = !cur_item.ticked_off;
+                self.state.snapshot();
+                let changed = cur_item.set_ticked_recursive(ticked);
+                self.state.update_parent_ticks();
+                self.state.message = Some(format!("{} {} item(s)", if ticked {"Ticked"} else {"Unticked"}, changed));
+                self.state.set_changes(true);
+                self.dirty_window = true;
+            },
+            Key::Char('A') if !cur_list.is_empty() => {
+                // (un)tick every item at this level
+                let ticked = !cur_list.iter().all(|x| x.ticked_off);
+                self.state.snapshot();
+                let mut changed = 0;
+                for item in cur_list.iter_mut() {
+                    if item.set_ticked(ticked) {
+                        changed += 1;
+                    }
+                }
+                self.state.update_parent_ticks();
+                self.state.message = Some(format!("{} {} item(s)", if ticked {"Ticked"} else {"Unticked"}, changed));
+                self.state.set_changes(true);
+                self.dirty_window = true;
+            },
+            Key::Char('C') if !cur_list.is_empty() => {
+                // clear completed items at this level
+                let count = cur_list.iter().filter(|x| x.ticked_off).count();
+                if count > 0 {
+                    self.state.snapshot();
+                    let last = *self.state.last_cur()?;
+                    // keep the cursor on the same item if it survives
+                    let before = cur_list[..last].iter().filter(|x| x.ticked_off).count();
+                    cur_list.retain(|x| !x.ticked_off);
+                    if cur_list.is_empty() {
+                        if self.state.can_pop_cur() {
+                            self.state.pop_cur()?;
+                        } else {
+                            *self.state.last_cur()? = 0;
+                        }
+                    } else {
+                        *self.state.last_cur()? = (last - before).min(cur_list.len() - 1);
+                    }
+                    self.state.update_parent_ticks();
+                    self.state.set_changes(true);
+                    self.dirty_window = true;
+                }
+                self.state.message = Some(format!("Cleared {} completed item(s)", count));
+            },
+            Key::Char('t') if !cur_list.is_empty() => {
+                let cur_item = self.state.cur_item()?;
+                let current = cur_item.estimate.map_or(String::new(), duration::format_duration);
+                if let Some(estimate) = self.view.get_user_input_buf("Estimate (i.e. 2h, 30m, 1d; empty clears)", &current, None, InputKind::Estimate)? {
+                    if estimate.trim().is_empty() {
+                        self.state.snapshot();
+                        cur_item.estimate = None;
+                        self.state.set_changes(true);
+                    } else if let Some(minutes) = duration::parse_duration(&estimate) {
+                        self.state.snapshot();
+                        cur_item.estimate = Some(minutes);
+                        self.state.set_changes(true);
+                    } else {
+                        self.state.message = Some(format!("Invalid estimate '{}'", estimate));
+                    }
+                }
+            },
+            Key::Char('c') if !cur_list.is_empty() => {
+                // start/stop the timer, only one can run at a time
+                if self.state.cur_item()?.is_timing() {
+                    self.state.cur_item()?.stop_timers();
+                    self.state.message = Some("Stopped timer".to_owned());
+                } else {
+                    self.state.cur_loaded_list().stop_timers();
+                    self.state.cur_item()?.start_timer();
+                    self.state.message = Some("Started timer".to_owned());
+                }
+                self.state.set_changes(true);
+            },
+            Key::Char('D') if !cur_list.is_empty() => {
+                let cur_item = self.state.cur_item()?;
+                let current = cur_item.due.map_or(String::new(), |x| x.to_string());
+                if let Some(date) = self.view.get_user_input_buf("Due (YYYY-MM-DD, today, tomorrow; empty clears)", &current, None, InputKind::Date)? {
+                    if date.trim().is_empty() {
+                        self.state.snapshot();
+                        cur_item.due = None;
+                        self.state.set_changes(true);
+                    } else {
+                        match util::parse_day(&date) {
+                            Ok(due) => {
+                                self.state.snapshot();
+                                cur_item.due = Some(due);
+                                self.state.set_changes(true);
+                            },
+                            Err(err) => self.state.message = Some(err.to_string()),
+                        }
+                    }
+                }
+            },
+            Key::Char('r') if !cur_list.is_empty() => {
+                let cur_item = self.state.cur_item()?;
+                let current = cur_item.recur.as_ref().map_or(String::new(), Recurrence::to_string);
+                if let Some(rule) = self.view.get_user_input_buf("Repeat (i.e. every 2 weeks, every month on the 1st, 3 days after completion)", &current, None, InputKind::Recurrence)? {
+                    if rule.trim().is_empty() {
+                        self.state.snapshot();
+                        cur_item.recur = None;
+                        self.state.set_changes(true);
+                    } else if let Some(recur) = Recurrence::parse(&rule) {
+                        self.state.snapshot();
+                        cur_item.recur = Some(recur);
+                        self.state.set_changes(true);
+                    } else {
+                        self.state.message = Some(format!("Invalid repeat '{}'", rule));
+                    }
                 }
             },
+            Key::Char('m') if !cur_list.is_empty() => {
+                // mark the item for 'b'
+                let path = self.state.cur_path();
+                let had_id = self.state.cur_item()?.id.is_some();
+                if let Some(id) = dependency::ensure_id(self.state.cur_loaded_list(), &path) {
+                    self.state.mark = Some(Reference {
+                        list: Some(self.state.cur_list_reference_path()),
+                        id,
+                    });
+                    self.state.message = Some(format!("Marked '{}'", self.state.cur_item()?.title));
+                    if !had_id {
+                        self.state.set_changes(true);
+                    }
+                }
+            },
+            Key::Char('b') if !cur_list.is_empty() => {
+                // toggle whether the current item depends on the marked one
+                if let Some(mark) = self.state.mark.clone() {
+                    let same_list = mark.list.as_ref().is_some_and(|x| util::same_path(x, &self.state.cur_loaded_list().path));
+                    let reference = if same_list {
+                        Reference { list: None, id: mark.id.clone() }.to_string()
+                    } else {
+                        mark.to_string()
+                    };
+                    let cur_item = self.state.cur_item()?;
+                    if same_list && cur_item.id.as_ref() == Some(&mark.id) {
+                        self.state.message = Some("An item can't depend on itself".to_owned());
+                    } else if cur_item.depends_on.contains(&reference) {
+                        self.state.snapshot();
+                        cur_item.depends_on.retain(|x| x != &reference);
+                        self.state.message = Some("Removed dependency".to_owned());
+                        self.state.set_changes(true);
+                    } else {
+                        self.state.snapshot();
+                        let path = self.state.cur_path();
+                        dependency::ensure_id(self.state.cur_loaded_list(), &path);
+                        cur_item.depends_on.push(reference);
+                        if let Some(cycle) = self.state.find_dependency_cycle() {
+                            self.state.cancel_snapshot();
+                            self.state.message = Some(format!("That would create a cycle: {}", cycle.join(" -> ")));
+                        } else {
+                            self.state.message = Some("Added dependency".to_owned());
+                            self.state.set_changes(true);
+                        }
+                    }
+                } else {
+                    self.state.message = Some("Mark an item with 'm' first".to_owned());
+                }
+            },
+            Key::Char('B') if !cur_list.is_empty() => {
+                // jump to the first unfinished item blocking this one
+                let from = self.state.cur_loaded_list().path.clone();
+                let blocking = self.state.cur_item()?.depends_on.iter()
+                    .map(|x| Reference::parse(x))
+                    .find(|x| self.state.lookup_dependency(&from, x).is_some_and(|x| !x.1));
+                match blocking {
+                    Some(Reference { list, id }) => self.go_to_item(list.as_ref().map(|x| &x[..]), &id)?,
+                    None => self.state.message = Some("Nothing is blocking this".to_owned()),
+                }
+            },
+            Key::Char('\n') if !cur_list.is_empty() => {
+                let title = self.state.cur_item()?.title.clone();
+                let mut links = link::parse_links(&title, &self.state.cur_loaded_list().path);
+                let choice = match links.len() {
+                    0 => {
+                        self.state.message = Some("There are no links in this item".to_owned());
+                        None
+                    },
+                    1 => Some(0),
+                    _ => {
+                        let prompt = format!("Follow which link? ({bold}q{reset}/{bold}esc{reset} to exit)", bold = style::Bold, reset = style::Reset);
+                        let choice = select_helper::select(&mut self.view, &mut stdin(), prompt, &links.iter().map(link::Link::label).collect())?;
+                        self.dirty_window = true;
+                        choice
+                    },
+                };
+                if let Some(choice) = choice {
+                    self.follow_link(links.swap_remove(choice))?;
+                }
+            },
+            Key::Char('[') => {
+                match self.state.jump_back() {
+                    Some(jump) => self.go_to(&jump)?,
+                    None => self.state.message = Some("Nothing to go back to".to_owned()),
+                }
+            },
+            Key::Char(']') => {
+                match self.state.jump_forward() {
+                    Some(jump) => self.go_to(&jump)?,
+                    None => self.state.message = Some("Nothing to go forward to".to_owned()),
+                }
+            },
+            Key::Char('+') if !cur_list.is_empty() => {
+                let cur_item = self.state.cur_item()?;
+                self.state.snapshot();
+                cur_item.priority = cur_item.priority.raise();
+                self.state.set_changes(true);
+            },
+            Key::Char('-') if !cur_list.is_empty() => {
+                let cur_item = self.state.cur_item()?;
+                self.state.snapshot();
+                cur_item.priority = cur_item.priority.lower();
+                self.state.set_changes(true);
+            },
+            Key::Char('p') => self.sort_level(false, &sort::by_priority)?,
+            Key::Char('P') => self.sort_level(true, &sort::by_priority)?,
             Key::Char('i') => {
                 // new item
-                if let Some(new_title) = self.view.get_user_input("New Item", false)? {
+                if let Some(new_title) = self.view.get_user_input("New Item", InputKind::Item)? {
+                    self.state.snapshot();
                     let new_item = todo_list::TodoItem::create(new_title);
                     cur_list.insert(*self.state.last_cur()?, new_item);
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                     self.dirty_window = true;
                 }
             },
             Key::Char('a') => {
                 // append new item
-                if let Some(new_title) = self.view.get_user_input("New Item", false)? {
+                if let Some(new_title) = self.view.get_user_input("New Item", InputKind::Item)? {
+                    self.state.snapshot();
                     let new_item = todo_list::TodoItem::create(new_title);
                     {let last = self.state.last_cur()?;
                     *last = if *last + 1 <= cur_list.len() {*last + 1} else {*last};
                     cur_list.insert(*last, new_item);}
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                     self.dirty_window = true;
                 }
             },
             Key::Char('d') => {
                 // delete item
                 if cur_list.len() > 0 {
-                    self.state.history = Some(cur_list.remove(*self.state.last_cur()?));
+                    self.state.snapshot();
+                    let item = cur_list.remove(*self.state.last_cur()?);
+                    self.store_register(vec![item.clone()], "Cut")?;
+                    self.state.history = Some(item);
                     let last = self.state.last_cur()?.clone();
                     if last >= cur_list.len() {
                         if cur_list.len() == 0 {
-                            if self.state.cur_depth() > 1 {
+                            if self.state.can_pop_cur() {
                                 self.state.pop_cur()?;
                             }
                         } else {
@@ -116,31 +571,88 @@ impl Window {
                         }
                         self.dirty_window = true;
                     }
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                 }
             },
             Key::Char('u') => {
                 if let Some(item) = self.state.history.clone() {
+                    self.state.snapshot();
                     cur_list.insert(*self.state.last_cur()?, item);
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                     self.dirty_window = true;
                 }
                 self.state.history = None;
             },
+            Key::Char('M') if !cur_list.is_empty() => {
+                // move to another list
+                let from = self.state.cur_loaded_list().path.clone();
+                let lists: Vec<(::std::path::PathBuf, String)> = super::find_lists()?.into_iter()
+                    .filter(|x| !util::same_path(&x.0.to_string_lossy(), &from))
+                    .collect();
+                let title = self.state.cur_item()?.title.clone();
+                let prompt = format!("Move '{}' to which list? ({bold}q{reset}/{bold}esc{reset} to exit)", title, bold = style::Bold, reset = style::Reset);
+                let names = lists.iter().map(|x| x.1.clone()).collect();
+                self.dirty_window = true;
+                if lists.is_empty() {
+                    self.state.message = Some("There are no other lists".to_owned());
+                } else if let Some(choice) = select_helper::select(&mut self.view, &mut stdin(), prompt, &names)? {
+                    let dest_path = lists[choice].0.to_string_lossy().to_string();
+                    let mut dest = match self.state.loaded_list(&dest_path) {
+                        Some(list) => list,
+                        None => WindowState::load_list(&lists[choice].0)?,
+                    };
+                    let (paths, mut titles): (Vec<Vec<usize>>, Vec<String>) = transfer::item_paths(&dest.contents).into_iter().unzip();
+                    titles.insert(0, "<Top level>".to_owned());
+                    let prompt = format!("Put it under? ({bold}q{reset}/{bold}esc{reset} to exit)", bold = style::Bold, reset = style::Reset);
+                    if let Some(parent) = select_helper::select(&mut self.view, &mut stdin(), prompt, &titles)? {
+                        let parent = if parent == 0 {vec![]} else {paths[parent - 1].clone()};
+                        let path = self.state.cur_path();
+                        if let Err(err) = transfer::move_item(self.state.cur_loaded_list(), &path, &mut dest, &parent) {
+                            // the file may not match the list if it couldn't be written back
+                            self.state.set_changes(true);
+                            return Err(err);
+                        }
+                        self.state.list_saved(&dest);
+                        // both lists were saved
+                        self.state.set_changes(false);
+                        self.state.set_destructive_changes(false);
+                        self.state.clear_history();
+                        self.state.clamp_cur();
+                        self.state.message = Some(format!("Moved '{}' to {}", title, dest.name));
+                    }
+                }
+            },
+            Key::Char('"') => {
+                // picks the register for the next yank, cut or paste
+                match next_key() {
+                    Some(Ok(Key::Char(name))) if name.is_ascii_alphabetic() || name == '"' || name == '+' => {
+                        self.state.pending_register = Some(name);
+                        self.state.message = Some(format!("Using register \"{}", name));
+                    },
+                    _ => self.state.message = Some("Registers are a-z (A-Z to append), '\"' and '+'".to_owned()),
+                }
+            },
+            Key::Char('y') if !cur_list.is_empty() => {
+                let item = self.state.cur_item()?.clone();
+                self.store_register(vec![item], "Yanked")?;
+            },
+            Key::Char('v') => self.paste(Paste::After)?,
+            Key::Char('V') => self.paste(Paste::Before)?,
+            Key::Ctrl('v') => self.paste(Paste::Child)?,
             Key::Char('g') => {
-                if let Some(mut goto_loc) = self.view.get_user_input("Goto # (1-indexed): ", false)? {
+                if let Some(mut goto_loc) = self.view.get_user_input("Goto # (1-indexed): ", InputKind::Goto)? {
                     // try to parse int
                     let last = self.state.last_cur()?;
                     if let Some(id) = goto_loc.find('-') {
                         goto_loc.remove(id);
                         match goto_loc.parse::<usize>() {
                             Ok(num) if num > 0 => *last = if num <= cur_list.len() {cur_list.len() - num} else {0},
-                            _ => print!("\u{7}\u{7}"), // beep
+                            _ => self.state.message = Some(format!("Invalid item number '-{}'", goto_loc)),
                         }
                     } else {
                         match goto_loc.parse::<usize>() {
                             Ok(num) if num > 0 => *last = if num <= cur_list.len() {num - 1} else {cur_list.len() - 1},
-                            _ => print!("\u{7}\u{7}"), // beep
+                            _ => self.state.message = Some(format!("Invalid item number '{}'", goto_loc)),
                         }
                     }
                     self.dirty_window = true;
@@ -149,6 +661,7 @@ impl Window {
             Key::Char('K') => {
                 // Move item up
                 if cur_list.len() > 1 {
+                    self.state.snapshot();
                     {
                     let last = self.state.last_cur()?;
                     if *last > 0 {
@@ -160,13 +673,14 @@ impl Window {
                         cur_list.insert(*last, item);
                     }
                     }
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                     self.dirty_window = true;
                 }
             },
             Key::Char('J') => {
                 // Move item down
                 if cur_list.len() > 1 {
+                    self.state.snapshot();
                     {
                     let last = self.state.last_cur()?;
                     if *last < cur_list.len() - 1 {
@@ -178,14 +692,15 @@ impl Window {
                         *last = 0;
                     }
                     }
-                    self.state.changes = true;
+                    self.state.set_changes(true);
                     self.dirty_window = true;
                 }
             },
             Key::Char('H') => {
                 if cur_list.len() > 0 {
+                    self.state.snapshot();
                     let mut last = self.state.last_cur()?;
-                    if self.state.cur_depth() > 1 && *self.state.cur(self.state.cur_depth() - 2)? > 0 {
+                    if self.state.can_pop_cur() && *self.state.cur(self.state.cur_depth() - 2)? > 0 {
                         let item = cur_list.remove(*last);
                         self.state.pop_cur()?;
                         let new_list = &mut self.state.cur_parent_list();
@@ -195,10 +710,11 @@ impl Window {
                     }
                     self.dirty_window = true;
                 }
-                self.state.changes = true;
+                self.state.set_changes(true);
             },
             Key::Char('L') => {
                 if cur_list.len() > 0 {
+                    self.state.snapshot();
                     let last = self.state.last_cur()?;
                     if *last > 0 {
                         let item = cur_list.remove(*last);
@@ -209,88 +725,291 @@ impl Window {
                     }
                     self.dirty_window = true;
                 }
-                self.state.changes = true;
+                self.state.set_changes(true);
             },
-            Key::PageDown => {
-                if cur_list.len() > 0 {
-                    let last = self.state.last_cur()?;
-                    *last = cur_list.len() - 1;
+            Key::Char('s') => {
+                let keys = [
+                    ("Title (A to Z)", sort::SortKey::Title, false),
+                    ("Title (Z to A)", sort::SortKey::Title, true),
+                    ("Unfinished first", sort::SortKey::Completion, false),
+                    ("Finished first", sort::SortKey::Completion, true),
+                    ("Oldest first", sort::SortKey::Created, false),
+                    ("Newest first", sort::SortKey::Created, true),
+                    ("Fewest children first", sort::SortKey::Children, false),
+                    ("Most children first", sort::SortKey::Children, true),
+                    ("Due soonest first", sort::SortKey::Due, false),
+                    ("Due latest first", sort::SortKey::Due, true),
+                    ("Highest priority first", sort::SortKey::Priority, false),
+                    ("Lowest priority first", sort::SortKey::Priority, true),
+                ];
+                let options = keys.iter().map(|x| x.0.to_owned()).collect();
+                let prompt = format!("Sort by? ({bold}q{reset}/{bold}esc{reset} to exit)", bold = style::Bold, reset = style::Reset);
+                if let Some(choice) = select_helper::select(&mut self.view, &mut stdin(), prompt, &options)? {
+                    let prompt = format!("Sort what? ({bold}q{reset}/{bold}esc{reset} to exit)", bold = style::Bold, reset = style::Reset);
+                    let scopes = vec!["Current level".to_owned(), "Current level and everything under it".to_owned()];
+                    if let Some(scope) = select_helper::select(&mut self.view, &mut stdin(), prompt, &scopes)? {
+                        let (_, key, descending) = keys[choice];
+                        self.sort_level(scope == 1, &|a: &todo_list::TodoItem, b: &todo_list::TodoItem| {
+                            let ord = sort::by_key(key, a, b);
+                            if descending {ord.reverse()} else {ord}
+                        })?;
+                    }
+                }
+            },
+            Key::Ctrl('z') => {
+                if self.state.undo() {
                     self.dirty_window = true;
                 }
             },
-            Key::PageUp => {
-                if cur_list.len() > 0 {
-                    let last = self.state.last_cur()?;
-                    *last = 0;
+            Key::Ctrl('y') => {
+                if self.state.redo() {
+                    self.dirty_window = true;
+                }
+            },
+            Key::Char('f') => {
+                let cur_filter = self.state.filter.as_ref().map_or(String::new(), |f| f.source.clone());
+                if let Some(expr) = self.view.get_user_input_buf("Filter by tags (empty clears)", &cur_filter, None, InputKind::Filter)? {
+                    self.state.filter = tags::TagFilter::parse(&expr);
+                    self.state.reset_cur_visible();
                     self.dirty_window = true;
                 }
             },
+            Key::Char('F') => {
+                self.state.hide_completed = !self.state.hide_completed;
+                self.state.check_cur_visible();
+                self.state.message = Some(if self.state.hide_completed {"Hiding completed items"} else {"Showing completed items"}.to_owned());
+                self.dirty_window = true;
+            },
+            Key::Ctrl('f') | Key::Ctrl('b') | Key::PageDown | Key::PageUp | Key::Ctrl('n') | Key::Ctrl('u') => {
+                // a page (or half of one) at a time, the view scrolls along with the cursor
+                let page = self.page_rows();
+                let amount = match event {
+                    Key::Ctrl('f') | Key::Ctrl('b') | Key::PageDown | Key::PageUp => page,
+                    _ => page / 2,
+                }.max(1).saturating_mul(count);
+                let down = event == Key::Ctrl('f') || event == Key::PageDown || event == Key::Ctrl('n');
+                self.state.move_cur_rows(amount, down);
+                self.offset = if down {self.offset.saturating_add(amount)} else {self.offset.saturating_sub(amount)};
+                self.dirty_window = true;
+            },
+            Key::Char('z') => {
+                // 'zz', 'zt' and 'zb' scroll the cursor to the middle, top or bottom
+                let height = self.page_rows();
+                let rows = self.state.visible_rows();
+                let cur = self.state.cur_row(&rows).unwrap_or(0);
+                let margin = Self::scroll_off(height);
+                match next_key() {
+                    Some(Ok(Key::Char('z'))) => self.offset = cur.saturating_sub(height / 2),
+                    Some(Ok(Key::Char('t'))) => self.offset = cur.saturating_sub(margin),
+                    Some(Ok(Key::Char('b'))) => self.offset = (cur + margin + 1).saturating_sub(height),
+                    _ => self.state.message = Some("'z' is followed by 'z' (middle), 't' (top) or 'b' (bottom)".to_owned()),
+                }
+                self.dirty_window = true;
+            },
             // System Commands
             Key::Ctrl('r') => {
-                if dialoguer::Confirmation::new("Reset to disk?").interact()? {
+                if self.view.confirm("Reset to disk?")? {
+                    self.state.snapshot();
                     self.state.reload_list()?;
                 }
             },
             Key::Ctrl('s') => {
                 self.state.save_list()?;
-                self.state.changes = false;
-                self.state.destructive_changes = false;
+                self.state.set_changes(false);
+                self.state.set_destructive_changes(false);
             },
             Key::Ctrl('S') => {
-                if let Some(new_path) = self.view.get_user_input("Path to save to: ", false)? {
+                if let Some(new_path) = self.view.get_user_input("Path to save to: ", InputKind::Path)? {
                     self.state.cur_loaded_list().path = new_path;
                     self.state.save_list()?;
-                    self.state.changes = false;
-                    self.state.destructive_changes = false;
+                    self.state.set_changes(false);
+                    self.state.set_destructive_changes(false);
                 }
             },
             Key::Ctrl('c') => {
-                if self.state.changes && dialoguer::Confirmation::new("Save current to disk?").interact()? {
-                    self.state.save_list()?;
-                }
                 self.view.clear()?;
                 if let Some(new_list) = super::change_list()? {
                     self.state.switch_list(new_list);
+                    self.state.check_dependencies();
+                } else {
+                    self.state.message = Some("Didn't switch list".to_owned());
+                }
+                self.dirty_window = true;
+            },
+            Key::Char('.') if !cur_list.is_empty() => {
+                self.state.hoist();
+                self.dirty_window = true;
+            },
+            Key::Char(',') => {
+                if self.state.unhoist() {
+                    self.dirty_window = true;
+                } else {
+                    self.state.message = Some("Nothing is hoisted ('.' hoists the current item)".to_owned());
+                }
+            },
+            Key::Char('|') | Key::Char('_') => {
+                if self.split.is_some() {
+                    self.state.message = Some("Already split ('Q' closes it)".to_owned());
+                } else {
+                    self.split = Some(Split {
+                        state: self.state.new_pane(),
+                        offset: self.offset,
+                        vertical: event == Key::Char('|'),
+                        focus_first: true,
+                    });
+                }
+            },
+            Key::Char('`') => {
+                if self.split.is_some() {
+                    self.switch_pane();
+                    self.dirty_window = true;
                 } else {
-                    bail!("Failed to switch list")
+                    self.state.message = Some("There is only one pane ('|' or '_' splits)".to_owned());
+                }
+            },
+            Key::Char('Q') => {
+                // closes the other pane, the lists only it had open become tabs here
+                if let Some(split) = self.split.take() {
+                    self.state.adopt_tabs(split.state);
                 }
             },
+            Key::Char('>') => {
+                let next = (self.state.cur_tab() + 1) % self.state.tab_count();
+                self.state.switch_tab(next);
+                self.dirty_window = true;
+            },
+            Key::Char('<') => {
+                let count = self.state.tab_count();
+                let prev = (self.state.cur_tab() + count - 1) % count;
+                self.state.switch_tab(prev);
+                self.dirty_window = true;
+            },
+            Key::Ctrl('w') => {
+                if self.state.tab_count() <= 1 {
+                    self.state.message = Some("Can't close the last tab ('escape' quits)".to_owned());
+                } else {
+                    if self.state.changes() && self.view.confirm("Save current to disk?")? {
+                        self.state.save_list()?;
+                    }
+                    self.state.close_tab();
+                    self.dirty_window = true;
+                }
+            },
+            Key::Char('W') => {
+                let count = self.state.save_all()?;
+                self.state.message = Some(format!("Saved {} list(s)", count));
+            },
             Key::Ctrl('d') => {
-                println!("If you change your mind you can always press 's' to resave this current list as it will just delete the file.\nBut as soon as you switch out the list (and choose not to save) or you quit it is gone forever!!!");
-                if dialoguer::Confirmation::new("You sure you want to delete this list?").interact()? {
+                self.state.message = Some("'ctrl + s' saves it again, but once you switch away from it (without saving) or quit it's gone forever".to_owned());
+                self.draw()?;
+                self.view.flush()?;
+                self.state.message = None;
+                if self.view.confirm("You sure you want to delete this list?")? {
                     remove_file(&self.state.cur_loaded_list().path)?;
-                    self.state.changes = true;
-                    self.state.destructive_changes = true;
+                    self.state.set_changes(true);
+                    self.state.set_destructive_changes(true);
+                }
+            },
+            Key::Ctrl('a') => {
+                let list = self.state.cur_loaded_list();
+                if archive::is_archive(&list.path) {
+                    self.state.message = Some("This list is already an archive".to_owned());
+                } else if let Some(date) = self.view.get_user_input("Archive items completed before (YYYY-MM-DD, empty for all)", InputKind::Date)? {
+                    let before = if date.trim().is_empty() {None} else {Some(util::parse_date(&date)?)};
+                    let count = archive::archive_finished(list, before)?;
+                    let message = format!("Archived {} item(s) into {}", count, archive::archive_path(&list.path));
+                    // both lists were saved
+                    self.state.set_changes(false);
+                    self.state.set_destructive_changes(false);
+                    self.state.clear_history();
+                    self.state.clamp_cur();
+                    self.state.message = Some(message);
+                    self.dirty_window = true;
+                }
+            },
+            Key::Char('R') if !cur_list.is_empty() => {
+                let list = self.state.cur_loaded_list();
+                if archive::is_archive(&list.path) {
+                    let original = archive::original_path(&list.path);
+                    let mut dest = match self.state.loaded_list(&original) {
+                        Some(list) => list,
+                        None => WindowState::load_list(::std::path::Path::new(&original))
+                            .chain_err(|| format!("Can't load the original list '{}'", original))?,
+                    };
+                    archive::restore(list, &mut dest, &self.state.cur_path())?;
+                    self.state.list_saved(&dest);
+                    let message = format!("Restored item into {}", original);
+                    self.state.clamp_cur();
+                    // both lists were saved
+                    self.state.set_changes(false);
+                    self.state.set_destructive_changes(false);
+                    self.state.clear_history();
+                    self.state.message = Some(message);
+                    self.dirty_window = true;
+                } else {
+                    self.state.message = Some("Only items in an archive (.archive.todo) can be restored".to_owned());
                 }
             },
             Key::Ctrl('p') => {
                 {
                 let mut list = self.state.cur_loaded_list();
-                if let Some(new_title) = self.view.get_user_input_buf("Edit Title", &list.name, None, false)? {
+                if let Some(new_title) = self.view.get_user_input_buf("Edit Title", &list.name, None, InputKind::Name)? {
                     list.name = new_title;
                 }
                 }
-                self.state.changes = true;
+                self.state.set_changes(true);
             },
             Key::Ctrl('h') => {
                 self.view.clear()?;
                 println!("{}", indoc!("
                 Help Information for todo (Any key exists this view)\r
-                - 'up' and 'k' / 'down' and 'j' arrow keys navigate the list verticaly\r
+                - 'up' and 'k' / 'down' and 'j' arrow keys navigate the list verticaly (a number before them repeats it)\r
                 - 'left' and 'h' / 'right' and 'l' navigate horizontally\r
                 - 'tab' starts a new inner list\r
                 - 'K'/'J' move the current item up/down\r
                 - 'H'/'L' moves the current item out/in\r
+                - '.' hoists the current item so only it is shown (as if it were the whole list) and ',' goes back\r
+                - 'ctrl + f'/'ctrl + b' (or 'page down'/'page up') move a page down/up and 'ctrl + n'/'ctrl + u' half a page (into and out of inner lists)\r
+                - clicking an item moves to it, clicking its box toggles it, double clicking edits it and dragging moves it (left/right to change its level)\r
+                - 'zz'/'zt'/'zb' scroll so the current item is in the middle/at the top/at the bottom\r
                 - 'g' allows you to go to a specific item (negative indexes go from end backwards)\r
-                - 'd' deletes the current item and places it into buffer for 'u'\r
+                - 'f' filters by a tag expression i.e. `#work !#done | #urgent` (empty clears it)\r
+                - 'F' hides/shows completed items (hide_completed in the config starts with them hidden)\r
+                - 'd' cuts the current item into the register and the buffer for 'u'\r
+                - 'y' yanks (copies) the current item and everything under it\r
+                - 'v'/'V' paste after/before the current item and 'ctrl + v' pastes as its last child\r
+                - '\"' then a letter picks the register for the next 'y', 'd' or paste (upper case appends)\r
+                - '\"+' copies to the system clipboard with OSC 52 (always on with osc52_clipboard in the config)\r
                 - 'u' inserts currently deleted item\r
                 - 'i' inserts a new item at the given index\r
                 - 'a' appends a new item after the given index\r
                 - 'space' will toggle the tick\r
+                - 'T' toggles the tick on the current item and everything under it\r
+                - 'A' toggles the tick on every item at the current level\r
+                - 'C' clears all completed items at the current level\r
+                - 'x'/'X' cycle the status forwards/backwards (todo, in progress, waiting, ...)\r
+                - 't' sets the time estimate of the current item (i.e. 2h, 30m, 1d)\r
+                - 'c' starts/stops the timer on the current item\r
+                - 'D' sets the due date of the current item\r
+                - 'r' makes the current item repeat (i.e. every week) once ticked off\r
+                - 'm' marks the current item and 'b' makes the current item depend on the marked one\r
+                - 'B' jumps to the item blocking the current one\r
+                - 'enter' follows a link in the current item ([[#id]], [[other.todo#id]], [[file]] or a url)\r
+                - '['/']' go back/forward through the places links were followed from\r
+                - '+'/'-' raise/lower the priority of the current item\r
+                - 'p'/'P' sort the current level (or everything under it) by priority\r
+                - 's' sorts the current level (or everything under it) by a chosen order\r
+                - 'ctrl + z' undoes the last change and 'ctrl + y' redoes it\r
                 - 'e' edits the current item at the end of the buffer\r
                 - 'E' edits the current item at the start\r
                 - 'w' wipes the item before editing it\r
-                - 'ctrl + c' changes the current todo list\r
+                - 'M' moves the current item (and everything under it) into another list\r
+                - 'ctrl + a' archives ticked off items into <list>.archive.todo\r
+                - 'R' restores the current item from an archive back into its list\r
+                - 'ctrl + c' opens another todo list in a new tab\r
+                - '>'/'<' switch to the next/previous tab and 'ctrl + w' closes the current one\r
+                - 'W' saves every list with unsaved changes\r
+                - '|'/'_' split the view side by side/one above the other, '`' switches pane and 'Q' closes the other one\r
                 - 'ctrl + d' deletes the current list\r
                 - 'ctrl + p' edits the current list title\r
                 - 'ctrl + r' resets back to disk\r
@@ -298,7 +1017,7 @@ impl Window {
                 - 'ctrl + S' save as\r
                 - 'ctrl + h' shows this message\r
                 - 'escape' or 'ctrl + q' exits the list\r"));
-                ::std::io::stdin().keys().next();
+                next_key();
             }
             _ => return Ok(true),
         }
@@ -1,33 +1,66 @@
-use std::io::{Write, stdin};
+use std::io::{ErrorKind, Write, stdin};
 
 use termion::event::{Key, Event};
-use termion::{color, style};
+use termion::style;
 use termion::input::{TermRead};
 
-use todo_list;
+use config::Config;
 use errors::*;
-use dialoguer;
+use theme;
+use symbols;
 
 pub mod window_view;
 pub mod window_state;
+pub mod input_history;
 mod mouse_event;
 mod key_event;
+mod resize;
 
 pub use self::window_view::*;
 pub use self::window_state::*;
+pub use self::input_history::InputKind;
+
+// Rows kept between the cursor and the top/bottom of a pane when scrolling
+const SCROLL_OFF: usize = 3;
+
+// The pane of a split that doesn't have focus
+pub struct Split {
+    state: WindowState,
+    offset: usize,
+    // side by side rather than one above the other
+    vertical: bool,
+    // the focused pane is the left/top one
+    focus_first: bool,
+}
 
 pub struct Window {
     state: WindowState,
     view: WindowView,
     dirty_window: bool,
+    offset: usize,
+    split: Option<Split>,
+    // the item the left mouse button was pressed on
+    drag: Option<mouse_event::Drag>,
+    last_click: Option<mouse_event::Click>,
 }
 
 impl Window {
-    pub fn new(state: WindowState) -> Result<Self> {
+    pub fn new(mut state: WindowState) -> Result<Self> {
+        state.config = Config::load()?;
+        state.hide_completed = state.config.hide_completed;
+        state.check_cur_visible();
+        if !theme::problems().is_empty() {
+            state.message = Some(format!("Problems with the theme: {}", theme::problems().join(", ")));
+        }
+        state.check_dependencies();
         Ok(Window {
             state: state,
             view: WindowView::new()?,
             dirty_window: false,
+            offset: 0,
+            split: None,
+            drag: None,
+            last_click: None,
         })
     }
 
@@ -35,75 +68,169 @@ impl Window {
         Self::new(WindowState::new_from_list(path)?)
     }
 
-    fn calc_item_length(parent: &todo_list::TodoItem) -> usize {
-        let mut sum = 1;
-        for item in parent.contents.iter() {
-            sum += Self::calc_item_length(item);
+    // The margin shrinks on small panes so the cursor can still reach every row
+    fn scroll_off(height: usize) -> usize {
+        SCROLL_OFF.min(height.saturating_sub(1) / 2)
+    }
+
+    // Moves `offset` (in rows) just enough to keep the cursor on screen and out of the margins
+    fn scroll(state: &WindowState, offset: usize, height: usize) -> usize {
+        let rows = state.visible_rows();
+        let max = rows.len().saturating_sub(height);
+        let cur = match state.cur_row(&rows) {
+            Some(cur) if height > 0 => cur,
+            _ => return offset.min(max),
+        };
+        let margin = Self::scroll_off(height);
+        let mut offset = offset;
+        if cur < offset + margin {
+            offset = cur.saturating_sub(margin);
+        } else if cur + margin >= offset + height {
+            offset = cur + margin + 1 - height;
+        }
+        offset.min(max)
+    }
+
+    // Rows of items in the focused pane
+    fn page_rows(&self) -> usize {
+        WindowView::item_rows(&self.state, self.layout().0)
+    }
+
+    // Rows used for the lists, under them is the status line and a row for prompts
+    fn list_rows(&self) -> u16 {
+        self.view.size.1.saturating_sub(3)
+    }
+
+    // (focused, other) panes
+    fn layout(&self) -> (Rect, Option<Rect>) {
+        let full = Rect { x: 1, y: 1, width: self.view.size.0, height: self.list_rows() };
+        match self.split {
+            None => (full, None),
+            Some(ref split) => {
+                let (first, second) = if split.vertical {
+                    // one column between them for the separator
+                    let width = full.width.saturating_sub(1) / 2;
+                    (Rect { width, ..full },
+                     Rect { x: width + 2, width: full.width.saturating_sub(width + 1), ..full })
+                } else {
+                    let height = full.height / 2;
+                    (Rect { height, ..full },
+                     Rect { y: height + 1, height: full.height - height, ..full })
+                };
+                if split.focus_first {(first, Some(second))} else {(second, Some(first))}
+            },
+        }
+    }
+
+    fn draw(&mut self) -> Result<()> {
+        let (focused, other) = self.layout();
+        self.view.print_out_list(&mut self.state, self.offset, focused, true)?;
+        if let (Some(ref mut split), Some(rect)) = (self.split.as_mut(), other) {
+            // the lists are shared so the other cursor may need to catch up
+            split.state.clamp_cur();
+            split.state.check_cur_visible();
+            split.offset = Self::scroll(&split.state, split.offset, WindowView::item_rows(&split.state, rect));
+            self.view.print_out_list(&mut split.state, split.offset, rect, false)?;
+            if split.vertical {
+                let x = focused.x.min(rect.x) + focused.width.min(rect.width);
+                for y in 1 .. focused.height + 1 {
+                    self.view.put(x, y, symbols::separator().to_owned());
+                }
+            }
+        }
+        let (width, rows) = (self.view.size.0, self.list_rows());
+        self.view.print_status_line(&self.state, rows + 1, width)?;
+        self.view.present()?;
+        // prompts go under the status line
+        self.view.move_cursor(1, rows + 2)
+    }
+
+    // Swaps the focus to the other pane of a split
+    fn switch_pane(&mut self) {
+        if let Some(ref mut split) = self.split {
+            split.state.mark = self.state.mark.clone();
+            ::std::mem::swap(&mut self.state, &mut split.state);
+            ::std::mem::swap(&mut self.offset, &mut split.offset);
+            split.focus_first = !split.focus_first;
+            self.state.clamp_cur();
         }
-        sum
+    }
+
+    // Errors from handling an event are shown on the status line rather than quitting,
+    // returns whether the event was unrecognised
+    fn handled(&mut self, res: Result<bool>) -> bool {
+        let unrecognised = match res {
+            Ok(unrecognised) => unrecognised,
+            Err(err) => {
+                self.state.message = Some(format!("{}Error: {}{}", theme::get().error, err, style::Reset));
+                self.dirty_window = true;
+                false
+            },
+        };
+        self.state.check_hoist();
+        self.state.check_cur_visible();
+        unrecognised
     }
 
     pub fn run(&mut self) -> Result<()> {
-        let mut amount = self.view.size.1 as usize - 4;
-        let mut offset = 0;
-        self.view.print_out_list(&mut self.state, 0, amount)?;
+        resize::install();
+        self.draw()?;
+        self.view.flush()?;
+        self.state.message = None;
         let mut events = stdin().events();
 
         loop {
             let c = events.next();
-            self.view.calc_size()?;
             let mut report_err = false;
 
             match c {
-                Some(Ok(Event::Mouse(mouse_event))) => report_err = self.handle_mouse_event(mouse_event)?,
+                // a resize interrupted the wait for input
+                Some(Err(ref err)) if err.kind() == ErrorKind::Interrupted => {},
+                Some(Ok(Event::Mouse(mouse_event))) => {
+                    let res = self.handle_mouse_event(mouse_event);
+                    report_err = self.handled(res);
+                },
                 Some(Ok(Event::Key(Key::Ctrl('q')))) | Some(Ok(Event::Key(Key::Esc))) => {
-                    if self.state.changes && dialoguer::Confirmation::new("Save current to disk?").interact()? {
+                    if let Some(split) = self.split.take() {
+                        self.state.adopt_tabs(split.state);
+                    }
+                    if self.state.tab_count() > 1 {
+                        if self.state.any_changes() && self.view.confirm("Save all changed lists to disk?")? {
+                            self.state.save_all()?;
+                        }
+                    } else if self.state.changes() && self.view.confirm("Save current to disk?")? {
                         self.state.save_list()?;
                     }
                     self.view.clear()?;
                     break;
                 },
-                Some(Ok(Event::Key(key))) => report_err = self.handle_key_event(key)?,
+                Some(Ok(Event::Key(key))) => {
+                    let res = self.handle_key_event(key);
+                    report_err = self.handled(res);
+                },
                 Some(_) => report_err = true,
                 _ => {},
             }
 
-            amount = self.view.size.1 as usize - 4;
+            if resize::take() {
+                self.view.calc_size()?;
+                self.view.invalidate();
+                self.dirty_window = true;
+            }
             if self.dirty_window {
-                offset = 0;
-                let mut full_offset = 0;
-                let parent = self.state.cur_loaded_list();
-                let index = *self.state.first_cur()?;
-                let list = &parent.contents;
-                let mut relative = 0;
-                while index > relative {
-                    // refactor
-                    let sum = Self::calc_item_length(&list[index - relative]);
-                    if amount >= sum && full_offset < amount - sum - 1 {
-                        full_offset += sum;
-                    } else {
-                        break;
-                    }
-                    relative += 1;
-                    offset += 1;
-                }
-                if index <= relative {
-                    offset = 0;
-                } else if index > offset {
-                    offset = index - offset;
-                }
+                self.offset = Self::scroll(&self.state, self.offset, self.page_rows());
                 self.dirty_window = false;
             }
 
-            self.view.print_out_list(&mut self.state, offset, amount)?;
-            self.view.set_cursor(false)?;
-            self.view.flush()?;
-
             if report_err {
-                write!(self.view, "\r\n{red}Unrecognised Key{reset}\r\n",
-                    red = color::Fg(color::Red),
-                    reset = style::Reset)?;
+                self.state.pending_count = None;
+                self.state.message = Some(format!("{}Unrecognised Key{}", theme::get().error, style::Reset));
             }
+            self.draw()?;
+            self.view.set_cursor(false)?;
+            self.view.flush()?;
+            // messages are only shown until the next key
+            self.state.message = None;
         }
         Ok(())
     }
@@ -1,38 +1,145 @@
-use std::ops::Rem;
-use termion::event::{MouseButton, MouseEvent};
+use std::time::{Duration, Instant};
+use termion::event::{Key, MouseButton, MouseEvent};
 use window::*;
 
+// Two clicks on the same item closer together than this edit it
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+// Columns each level of items is indented by
+const INDENT: u16 = 4;
+
+pub struct Drag {
+    path: Vec<usize>,
+    x: u16,
+}
+
+pub struct Click {
+    at: Instant,
+    path: Vec<usize>,
+}
+
 impl Window {
     pub fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<bool> {
-        let cur_list = self.state.cur_parent_list();
-        if cur_list.len() == 0 {return Ok(false);}
         match event {
             MouseEvent::Press(MouseButton::WheelDown, _, _) => {
-                // Better explanation
-                if cur_list.len() > 0 {
-                    let last = self.state.last_cur()?;
-                    if *last == !0 {
-                        *last = cur_list.len() - 1;
-                    } else {
-                        *last = ((*last as i64 - 1 + cur_list.len() as i64)
-                            % (cur_list.len() as i64)) as usize;
-                    }
-                    self.dirty_window = true;
-                }
+                self.state.move_cur_down(1)?;
+                self.dirty_window = true;
             },
             MouseEvent::Press(MouseButton::WheelUp, _, _) => {
-                if cur_list.len() > 0 {
-                    let last = self.state.last_cur()?;
-                    if *last == !0 {
-                        *last = 0;
-                    } else {
-                        *last = (*last as u64 + 1).rem(cur_list.len() as u64) as usize;
-                    }
-                    self.dirty_window = true;
+                self.state.move_cur_up(1)?;
+                self.dirty_window = true;
+            },
+            MouseEvent::Press(MouseButton::Left, x, y) => self.click(x, y)?,
+            MouseEvent::Hold(_, _) => {
+                if self.drag.is_some() {
+                    let title = self.state.cur_item()?.title.chars().take(20).collect::<String>();
+                    self.state.message = Some(format!("Moving '{}', let go where it should go (left/right changes the level)", title));
                 }
             },
+            MouseEvent::Release(x, y) => self.release(x, y)?,
             _ => return Ok(true),
         }
         Ok(false)
     }
-}
\ No newline at end of file
+
+    // Which pane (true for the focused one) and row of its `visible_rows` is at a position
+    fn row_at(&self, x: u16, y: u16) -> Option<(bool, usize)> {
+        let inside = |rect: Rect| x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height;
+        let (focused, other) = self.layout();
+        let (is_focused, rect, state, offset) = if inside(focused) {
+            (true, focused, &self.state, self.offset)
+        } else {
+            match (other, self.split.as_ref()) {
+                (Some(rect), Some(split)) if inside(rect) => (false, rect, &split.state, split.offset),
+                _ => return None,
+            }
+        };
+        let line = (y - rect.y) as usize;
+        let header = WindowView::header_rows(state);
+        if line < header { return None; }
+        Some((is_focused, offset + line - header))
+    }
+
+    // Moves the cursor to the item clicked on, clicking its box toggles it and clicking it twice edits it
+    fn click(&mut self, x: u16, y: u16) -> Result<()> {
+        self.drag = None;
+        let (focused, row) = match self.row_at(x, y) {
+            Some(res) => res,
+            None => return Ok(()),
+        };
+        if !focused {
+            self.switch_pane();
+        }
+        let path = match self.state.visible_rows().get(row) {
+            Some(row) => row.0.clone(),
+            None => return Ok(()),
+        };
+        self.state.set_cur(&path);
+        self.dirty_window = true;
+
+        // i.e. `    → [✓] title` the box is after the indent and the cursor
+        let indent = INDENT * (path.len() - 1 - self.state.hoist_depth()) as u16;
+        let col = x - self.layout().0.x;
+        let double = self.last_click.take().is_some_and(|x| x.path == path && x.at.elapsed() < DOUBLE_CLICK);
+        if col >= indent + 2 && col <= indent + 4 {
+            self.handle_key_event(Key::Char(' '))?;
+        } else if double {
+            self.handle_key_event(Key::Char('e'))?;
+        } else {
+            self.last_click = Some(Click { at: Instant::now(), path: path.clone() });
+            self.drag = Some(Drag { path, x });
+        }
+        Ok(())
+    }
+
+    // Drops a dragged item after the row above where it was let go,
+    // each indent moved left/right takes it a level out/in
+    fn release(&mut self, x: u16, y: u16) -> Result<()> {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return Ok(()),
+        };
+        let rows = self.state.visible_rows();
+        let (row, from_row) = match (self.row_at(x, y), self.state.cur_row(&rows)) {
+            (Some((true, row)), Some(from_row)) if rows[from_row].0 == drag.path => (row.min(rows.len() - 1), from_row),
+            _ => return Ok(()),
+        };
+        let shift = (x as i32 - drag.x as i32) / INDENT as i32;
+        // what it goes after, nothing for the very top
+        let anchor = if row > from_row {
+            Some(row)
+        } else if row < from_row || shift != 0 {
+            row.checked_sub(1)
+        } else {
+            // just a click
+            return Ok(());
+        };
+
+        let root = self.state.hoist_depth();
+        let (parent, index) = match anchor {
+            None => (self.state.hoist_path().to_vec(), 0),
+            Some(anchor) => {
+                let anchor = &rows[anchor].0;
+                let level = (drag.path.len() as i32 - 1 + shift).max(root as i32).min(anchor.len() as i32) as usize;
+                if level == anchor.len() {
+                    // the first item inside of it
+                    (anchor.clone(), 0)
+                } else {
+                    (anchor[..level].to_vec(), anchor[level] + 1)
+                }
+            },
+        };
+        if parent.starts_with(&drag.path) {
+            self.state.message = Some("Can't move an item into itself".to_owned());
+            return Ok(());
+        }
+        self.state.snapshot();
+        if let Err(err) = self.state.move_item(&drag.path, &parent, index) {
+            // neither an undo entry nor a half done move is left behind
+            self.state.cancel_snapshot();
+            return Err(err);
+        }
+        self.state.set_changes(true);
+        self.dirty_window = true;
+        Ok(())
+    }
+}
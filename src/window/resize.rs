@@ -0,0 +1,27 @@
+// Notices the terminal being resized through SIGWINCH rather than asking for its size after every event
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{mem, ptr};
+
+use libc;
+
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_resize(_: libc::c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+// Without SA_RESTART a read blocked on stdin is interrupted, so the resize is handled straight away
+pub fn install() {
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGWINCH, &action, ptr::null_mut());
+    }
+}
+
+// Whether the terminal was resized since this was last asked
+pub fn take() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
@@ -9,13 +9,14 @@ use glob::glob;
 use todo_list;
 use errors::*;
 use window_view::WindowView;
+use input_history::InputKind;
 use window_state::WindowState;
 use select_helper;
 use util;
 
 pub fn create_new_list(view: &mut WindowView) -> Result<Option<todo_list::TodoList>> {
     let mut stdin = stdin();
-    if let Some(list_name) = view.get_user_input("New List Name: ", false)? {
+    if let Some(list_name) = view.get_user_input("New List Name: ", InputKind::Name)? {
         let mut view = WindowView::new()?;
         let list_prompt = format!("Where to place the {underline}list{reset}? ({bold}q{reset}/{bold}esc{reset} to exit)",
                                    bold = style::Bold, reset = style::Reset,
@@ -32,11 +33,10 @@ pub fn create_new_list(view: &mut WindowView) -> Result<Option<todo_list::TodoLi
             0 => PathBuf::from("./"),
             1 => util::get_file_path()?,
             2 => {
-                if let Some(dir) = view.get_user_input("Enter directory: ", true)? {
+                if let Some(dir) = view.get_user_input("Enter directory: ", InputKind::Path)? {
                     PathBuf::from(dir)
                 } else {
                     view.set_cursor(true)?;
-                    println!("\nInvalid Directory");
                     return Ok(None);
                 }
             },
@@ -49,33 +49,37 @@ pub fn create_new_list(view: &mut WindowView) -> Result<Option<todo_list::TodoLi
         Ok(Some(todo_list::TodoList::create(list_name, file_path.to_str().unwrap().to_string())))
     } else {
         view.set_cursor(true)?;
-        println!("\nInvalid List Name");
         Ok(None)
     }
 }
 
-pub fn change_list() -> Result<Option<todo_list::TodoList>> {
-    // Find all the lists and prompt user for which one
-    let list;
-    let mut actual = vec![];
-    let mut possibilities = vec![];
-    let mut stdin = stdin();
-    let mut view = WindowView::new()?;
-    for entry in glob(&(util::get_file_path()?.to_str().unwrap().to_string() + "/*.todo")).chain_err(|| "Can't change list")? {
+// All the lists in the home directory (~/_todo_lists/) and the current directory
+// as (path, name to display)
+pub fn find_lists() -> Result<Vec<(PathBuf, String)>> {
+    let mut res = vec![];
+    for entry in glob(&(util::get_file_path()?.to_str().unwrap().to_string() + "/*.todo")).chain_err(|| "Can't find lists")? {
         let entry = entry?;
         if entry.extension().unwrap() == "todo" {
-            actual.push(entry.clone());
-            write!(::std::io::stderr(), "{:?}\n", entry.file_name())?;
-            possibilities.push("~/_todo_lists/".to_owned() + &entry.file_stem().unwrap().to_str().unwrap().to_string());
+            let name = "~/_todo_lists/".to_owned() + &entry.file_stem().unwrap().to_str().unwrap().to_string();
+            res.push((entry, name));
         }
     }
-    for entry in glob("./*.todo").chain_err(|| "Can't change list")? {
+    for entry in glob("./*.todo").chain_err(|| "Can't find lists")? {
         let entry = entry?;
         if entry.extension().unwrap() == "todo" {
-            actual.push(entry.clone());
-            possibilities.push("./".to_owned() + &entry.file_stem().unwrap().to_str().unwrap().to_string());
+            let name = "./".to_owned() + &entry.file_stem().unwrap().to_str().unwrap().to_string();
+            res.push((entry, name));
         }
     }
+    Ok(res)
+}
+
+pub fn change_list() -> Result<Option<todo_list::TodoList>> {
+    // Find all the lists and prompt user for which one
+    let list;
+    let mut stdin = stdin();
+    let mut view = WindowView::new()?;
+    let (actual, mut possibilities): (Vec<PathBuf>, Vec<String>) = find_lists()?.into_iter().unzip();
     possibilities.push("<New List>".to_owned());
     possibilities.push("<Open Other List>".to_owned());
 
@@ -97,7 +101,7 @@ pub fn change_list() -> Result<Option<todo_list::TodoList>> {
         }
     } else if choice.unwrap() == possibilities.len() - 1 {
         write!(view, "{}{}", termion::clear::All, termion::cursor::Goto(1, 1))?;
-        if let Some(directory) = view.get_user_input_buf("Enter Directory: ", ::std::env::current_dir()?.to_str().unwrap(), None, true)? {
+        if let Some(directory) = view.get_user_input_buf("Enter Directory: ", ::std::env::current_dir()?.to_str().unwrap(), None, InputKind::Path)? {
             list = WindowState::load_list(&PathBuf::from(directory.trim().to_string()))?;
         } else {
             bail!("Invalid Directory")
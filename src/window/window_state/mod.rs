@@ -1,65 +1,395 @@
-use std::io::{Write};
-use std::fs::{File, read_to_string};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::fs::canonicalize;
 
-use toml;
 use std::cell::RefCell;
+use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
 
 use errors::*;
 
 use todo_list;
+use tags::TagFilter;
+use config::Config;
+use dependency::{self, Reference};
 mod helper;
 pub use self::helper::*;
 
+const MAX_UNDO: usize = 100;
+const MAX_JUMPS: usize = 100;
+
+// The state of the list prior to some change
+struct Snapshot {
+    contents: Vec<todo_list::TodoItem>,
+    cur: Vec<usize>,
+}
+
+// A place the cursor was before following a link
+#[derive(Clone, Debug)]
+pub struct Jump {
+    pub list: String,
+    pub cur: Vec<usize>,
+}
+
+// What is kept for each open list (tab) while another one is shown
+#[derive(Default)]
+struct Tab {
+    cur: Vec<usize>,
+    hoist: Vec<usize>,
+    history: Option<todo_list::TodoItem>,
+}
+
+// The unsaved state and undo history of an open list, shared by both panes of a split
+#[derive(Default)]
+struct Edits {
+    changes: bool, // lines have changed
+    destructive_changes: bool, // i.e. File has been removed
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
 pub struct WindowState {
-    pub changes: bool, // lines have changed
-    pub destructive_changes: bool, // i.e. File has been removed
     cur: RefCell<Vec<RefCell<usize>>>,
-    loaded_lists: Vec<RefCell<todo_list::TodoList>>,
+    // the item shown as the root of the view, empty for the whole list
+    hoist: Vec<usize>,
+    // shared with the other pane of a split
+    loaded_lists: Vec<Rc<RefCell<todo_list::TodoList>>>,
     cur_list: usize,
+    // one for each of `loaded_lists`, the one for `cur_list` is out of date
+    tabs: Vec<Tab>,
+    // one for each of `loaded_lists`, shared like them
+    edits: Vec<Rc<RefCell<Edits>>>,
     // Note: this is bad, this should really be a list of changes
     // and we should be able to revert, I'm just lazy rn
     // and this is a pretty big undertaking to do properly.
     pub history: Option<todo_list::TodoItem>,
+    // only items matching (or with children matching) are shown
+    pub filter: Option<TagFilter>,
+    // completed items aren't shown
+    pub hide_completed: bool,
+    pub config: Config,
+    // shown once after the next redraw
+    pub message: Option<String>,
+    // the item 'b' makes the current item depend on
+    pub mark: Option<Reference>,
+    // lists referred to by dependencies that aren't loaded, by path
+    external_lists: RefCell<HashMap<String, Option<todo_list::TodoList>>>,
+    // canonical paths of files that exist, shared with the other pane of a split
+    canonical_paths: Rc<RefCell<HashMap<String, String>>>,
+    back_jumps: Vec<Jump>,
+    forward_jumps: Vec<Jump>,
+    // yanked/cut subtrees by register, '"' being the default one
+    registers: Rc<RefCell<HashMap<char, Vec<todo_list::TodoItem>>>>,
+    // chosen with '"' for the next yank, cut or paste
+    pub pending_register: Option<char>,
+    // typed before a movement to repeat it
+    pub pending_count: Option<usize>,
 }
 
 impl WindowState {
     pub fn new(list: todo_list::TodoList) -> Self {
         WindowState {
-            changes: false,
-            destructive_changes: false,
             cur: RefCell::from(vec![RefCell::from(0)]),
-            loaded_lists: vec![RefCell::from(list)],
+            hoist: vec![],
+            loaded_lists: vec![Rc::new(RefCell::from(list))],
             cur_list: 0,
+            tabs: vec![Tab::default()],
+            edits: vec![Rc::new(RefCell::from(Edits::default()))],
             history: None,
+            filter: None,
+            hide_completed: false,
+            config: Config::default(),
+            message: None,
+            mark: None,
+            external_lists: RefCell::from(HashMap::new()),
+            canonical_paths: Rc::new(RefCell::from(HashMap::new())),
+            back_jumps: vec![],
+            forward_jumps: vec![],
+            registers: Rc::new(RefCell::from(HashMap::new())),
+            pending_register: None,
+            pending_count: None,
         }
     }
 
+    // For the other pane of a split, it shares the open lists and registers but has its own cursor
+    pub fn new_pane(&self) -> Self {
+        WindowState {
+            cur: RefCell::from(vec![]),
+            hoist: vec![],
+            loaded_lists: self.loaded_lists.clone(),
+            cur_list: self.cur_list,
+            tabs: self.loaded_lists.iter().map(|_| Tab::default()).collect(),
+            edits: self.edits.clone(),
+            history: None,
+            filter: self.filter.clone(),
+            hide_completed: self.hide_completed,
+            config: self.config.clone(),
+            message: None,
+            mark: self.mark.clone(),
+            external_lists: RefCell::from(HashMap::new()),
+            canonical_paths: self.canonical_paths.clone(),
+            back_jumps: vec![],
+            forward_jumps: vec![],
+            registers: self.registers.clone(),
+            pending_register: None,
+            pending_count: None,
+        }.with_cur(&self.cur_path())
+    }
+
+    fn with_cur(self, cur: &[usize]) -> Self {
+        self.set_cur(cur);
+        self
+    }
+
     pub fn new_from_list(path: &String) -> Result<Self> {
         Ok(Self::new(Self::load_list(&PathBuf::from(path))?))
     }
 
-    pub fn load_list(path: &PathBuf) -> Result<todo_list::TodoList> {
-        let mut list : todo_list::TodoList = toml::from_str(&read_to_string(path)?).chain_err(|| "Failed to load list")?;
-        list.path = path.to_string_lossy().to_owned().to_string();
-        Ok(list)
+    pub fn load_list(path: &Path) -> Result<todo_list::TodoList> {
+        todo_list::TodoList::load(path)
     }
 
-    // TODO: update this to use an index and a list
+    // Opens the list in a new tab (or the tab it is already in)
     pub fn switch_list(&mut self, list: todo_list::TodoList) {
         if let Some(pos) = self.loaded_lists.iter().position(|ref r| r.borrow_mut().path == list.path) {
-            self.reset_cur();
-            self.history = None;
-            self.changes = false;
-            self.destructive_changes = false;
-            self.cur_list = pos;
+            self.switch_tab(pos);
         } else {
-            self.reset_cur();
-            self.history = None;
-            self.changes = false;
-            self.destructive_changes = false;
-            self.cur_list = self.loaded_lists.len();
-            self.loaded_lists.push(RefCell::from(list));
+            self.loaded_lists.push(Rc::new(RefCell::from(list)));
+            self.tabs.push(Tab::default());
+            self.edits.push(Rc::new(RefCell::from(Edits::default())));
+            self.switch_tab(self.loaded_lists.len() - 1);
+        }
+    }
+
+    fn stash_tab(&mut self) {
+        self.tabs[self.cur_list] = Tab {
+            cur: self.cur_path(),
+            hoist: ::std::mem::take(&mut self.hoist),
+            history: self.history.take(),
+        };
+    }
+
+    fn restore_tab(&mut self, pos: usize) {
+        let tab = ::std::mem::take(&mut self.tabs[pos]);
+        self.cur_list = pos;
+        self.history = tab.history;
+        self.hoist = tab.hoist;
+        self.set_cur(&tab.cur);
+        self.clamp_cur();
+    }
+
+    pub fn switch_tab(&mut self, pos: usize) {
+        if pos == self.cur_list || pos >= self.tabs.len() { return; }
+        self.stash_tab();
+        self.restore_tab(pos);
+    }
+
+    pub fn tab_count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn cur_tab(&self) -> usize {
+        self.cur_list
+    }
+
+    // (name, has unsaved changes) of every tab
+    pub fn tab_names(&self) -> Vec<(String, bool)> {
+        self.loaded_lists.iter().zip(self.edits.iter())
+            .map(|(list, edits)| (list.borrow().name.clone(), edits.borrow().changes))
+            .collect()
+    }
+
+    // Closes the current tab (unless it is the last one) without saving it
+    pub fn close_tab(&mut self) -> bool {
+        if self.tabs.len() <= 1 { return false; }
+        let pos = self.cur_list;
+        self.loaded_lists.remove(pos);
+        self.tabs.remove(pos);
+        self.edits.remove(pos);
+        self.restore_tab(pos.min(self.tabs.len() - 1));
+        true
+    }
+
+    // Takes over the tabs of a closed pane that aren't open here, along with their unsaved changes
+    pub fn adopt_tabs(&mut self, other: WindowState) {
+        for (list, edits) in other.loaded_lists.into_iter().zip(other.edits) {
+            if self.loaded_lists.iter().any(|x| Rc::ptr_eq(x, &list)) { continue; }
+            self.loaded_lists.push(list);
+            self.edits.push(edits);
+            self.tabs.push(Tab::default());
+        }
+    }
+
+    // Saves every tab with changes, returning how many were saved
+    pub fn save_all(&mut self) -> Result<usize> {
+        let mut count = 0;
+        for (list, edits) in self.loaded_lists.iter().zip(self.edits.iter()) {
+            let mut edits = edits.borrow_mut();
+            if !edits.changes { continue; }
+            list.borrow().save()?;
+            edits.changes = false;
+            edits.destructive_changes = false;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn any_changes(&self) -> bool {
+        self.edits.iter().any(|x| x.borrow().changes)
+    }
+
+    // Whether the current list has unsaved changes
+    pub fn changes(&self) -> bool {
+        self.edits[self.cur_list].borrow().changes
+    }
+
+    pub fn set_changes(&self, changes: bool) {
+        self.edits[self.cur_list].borrow_mut().changes = changes;
+    }
+
+    // Whether the file of the current list was removed
+    pub fn destructive_changes(&self) -> bool {
+        self.edits[self.cur_list].borrow().destructive_changes
+    }
+
+    pub fn set_destructive_changes(&self, destructive_changes: bool) {
+        self.edits[self.cur_list].borrow_mut().destructive_changes = destructive_changes;
+    }
+
+    // The open copy of the list at `path` (which may have unsaved changes)
+    pub fn loaded_list(&self, path: &str) -> Option<todo_list::TodoList> {
+        self.loaded_lists.iter().find(|x| self.same_path(&x.borrow().path, path)).map(|x| x.borrow().clone())
+    }
+
+    // Switches to the tab for the list at `path`, opening it if needed
+    pub fn open_list(&mut self, path: &str) -> Result<()> {
+        match self.loaded_lists.iter().position(|x| self.same_path(&x.borrow().path, path)) {
+            Some(pos) => self.switch_tab(pos),
+            None => {
+                let list = Self::load_list(Path::new(path))?;
+                self.switch_list(list);
+            },
+        }
+        Ok(())
+    }
+
+    pub fn location(&self) -> Jump {
+        Jump {
+            list: self.cur_loaded_list().path.clone(),
+            cur: self.cur_path(),
+        }
+    }
+
+    // Call before jumping somewhere to be able to come back with `jump_back`
+    pub fn push_jump(&mut self) {
+        let location = self.location();
+        self.back_jumps.push(location);
+        if self.back_jumps.len() > MAX_JUMPS {
+            self.back_jumps.remove(0);
+        }
+        self.forward_jumps.clear();
+    }
+
+    // The location to go to, the current one is kept to come back to
+    pub fn jump_back(&mut self) -> Option<Jump> {
+        let jump = self.back_jumps.pop()?;
+        let location = self.location();
+        self.forward_jumps.push(location);
+        Some(jump)
+    }
+
+    pub fn jump_forward(&mut self) -> Option<Jump> {
+        let jump = self.forward_jumps.pop()?;
+        let location = self.location();
+        self.back_jumps.push(location);
+        Some(jump)
+    }
+
+    // The register chosen for this operation, it only applies once
+    pub fn take_register(&mut self) -> char {
+        self.pending_register.take().unwrap_or('"')
+    }
+
+    // Upper case registers are appended to, the default register always gets a copy
+    pub fn set_register(&mut self, name: char, items: Vec<todo_list::TodoItem>) {
+        let mut registers = self.registers.borrow_mut();
+        if name != '"' {
+            registers.insert('"', items.clone());
+        }
+        if name.is_uppercase() {
+            registers.entry(name.to_ascii_lowercase()).or_default().extend(items);
+        } else {
+            registers.insert(name, items);
+        }
+    }
+
+    pub fn register(&self, name: char) -> Option<Vec<todo_list::TodoItem>> {
+        self.registers.borrow().get(&name.to_ascii_lowercase()).cloned()
+    }
+
+    fn take_snapshot(&self) -> Snapshot {
+        Snapshot {
+            contents: self.cur_loaded_list().contents.clone(),
+            cur: self.cur_path(),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.cur_loaded_list().contents = snapshot.contents;
+        self.set_cur(&snapshot.cur);
+        self.set_changes(true);
+    }
+
+    // Call before changing the list to be able to undo it
+    pub fn snapshot(&mut self) {
+        let snapshot = self.take_snapshot();
+        let mut edits = self.edits[self.cur_list].borrow_mut();
+        edits.undo_stack.push(snapshot);
+        if edits.undo_stack.len() > MAX_UNDO {
+            edits.undo_stack.remove(0);
+        }
+        edits.redo_stack.clear();
+    }
+
+    // Puts back the last snapshot as if the change after it never happened (it can't be redone)
+    pub fn cancel_snapshot(&mut self) {
+        let snapshot = self.edits[self.cur_list].borrow_mut().undo_stack.pop();
+        if let Some(snapshot) = snapshot {
+            self.cur_loaded_list().contents = snapshot.contents;
+            self.set_cur(&snapshot.cur);
+        }
+    }
+
+    // For changes that also went to another file (i.e. moving or archiving),
+    // undoing only this list would lose or double up items
+    pub fn clear_history(&self) {
+        let mut edits = self.edits[self.cur_list].borrow_mut();
+        edits.undo_stack.clear();
+        edits.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let snapshot = self.edits[self.cur_list].borrow_mut().undo_stack.pop();
+        match snapshot {
+            Some(snapshot) => {
+                let current = self.take_snapshot();
+                self.edits[self.cur_list].borrow_mut().redo_stack.push(current);
+                self.restore_snapshot(snapshot);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let snapshot = self.edits[self.cur_list].borrow_mut().redo_stack.pop();
+        match snapshot {
+            Some(snapshot) => {
+                let current = self.take_snapshot();
+                self.edits[self.cur_list].borrow_mut().undo_stack.push(current);
+                self.restore_snapshot(snapshot);
+                true
+            },
+            None => false,
         }
     }
 
@@ -74,11 +404,130 @@ impl WindowState {
         unsafe {&mut *res}
     }
 
+    // The current list as shared between tabs and panes
+    pub fn cur_shared_list(&self) -> Rc<RefCell<todo_list::TodoList>> {
+        self.loaded_lists[self.cur_list].clone()
+    }
+
     pub fn save_list(&self) -> Result<()> {
-        let list = self.cur_loaded_list();
-        let mut file = File::create(&list.path)?;
-        file.write_all(&toml::to_vec(&list)?)?;
-        Ok(())
+        self.cur_loaded_list().save()
+    }
+
+    // Should be called after changing the tick of the current item
+    pub fn update_parent_ticks(&self) {
+        if !self.config.auto_tick_parents { return; }
+        self.cur_loaded_list().update_parent_ticks(&self.cur_path());
+    }
+
+    // Like `util::canonical_path` but each file is only looked up once as this runs for every item
+    // with dependencies on every redraw (files that don't exist yet are looked up again)
+    fn canonical_path(&self, path: &str) -> String {
+        if let Some(res) = self.canonical_paths.borrow().get(path) {
+            return res.clone();
+        }
+        match canonicalize(path) {
+            Ok(res) => {
+                let res = res.to_string_lossy().to_string();
+                self.canonical_paths.borrow_mut().insert(path.to_owned(), res.clone());
+                res
+            },
+            Err(_) => path.to_owned(),
+        }
+    }
+
+    fn same_path(&self, a: &str, b: &str) -> bool {
+        a == b || self.canonical_path(a) == self.canonical_path(b)
+    }
+
+    // Runs `f` on the list at `path` preferring ones that are already loaded
+    fn with_list<R, F: FnOnce(&todo_list::TodoList) -> R>(&self, path: &str, f: F) -> Option<R> {
+        if let Some(list) = self.loaded_lists.iter().find(|x| self.same_path(&x.borrow().path, path)) {
+            return Some(f(&list.borrow()));
+        }
+        let mut external = self.external_lists.borrow_mut();
+        external.entry(path.to_owned())
+            .or_insert_with(|| Self::load_list(Path::new(path)).ok())
+            .as_ref().map(f)
+    }
+
+    // Should be called after `list` was changed and saved outside of the current list
+    pub fn list_saved(&mut self, list: &todo_list::TodoList) {
+        for (i, loaded) in self.loaded_lists.iter().enumerate() {
+            if i != self.cur_list && self.same_path(&loaded.borrow().path, &list.path) {
+                loaded.borrow_mut().contents = list.contents.clone();
+                // undoing would bring back what is no longer on disk
+                *self.edits[i].borrow_mut() = Edits::default();
+            }
+        }
+        self.external_lists.borrow_mut().retain(|path, _| !self.same_path(path, &list.path));
+    }
+
+    // The path stored in references to items in the current list
+    pub fn cur_list_reference_path(&self) -> String {
+        self.canonical_path(&self.cur_loaded_list().path)
+    }
+
+    // (title, finished, dependencies) of the item `reference` (written in the list at `from`) points to
+    pub fn lookup_dependency(&self, from: &str, reference: &Reference) -> Option<(String, bool, Vec<String>)> {
+        let path = reference.list.as_ref().map_or(from, |x| x);
+        self.with_list(path, |list| dependency::find_item(&list.contents, &reference.id)
+            .map(|x| (x.title.clone(), x.ticked_off, x.depends_on.clone())))?
+    }
+
+    // Titles of the unfinished items an item in the current list is waiting on
+    pub fn blocked_by(&self, item: &todo_list::TodoItem) -> Vec<String> {
+        let from = self.cur_loaded_list().path.clone();
+        item.depends_on.iter()
+            .filter_map(|x| self.lookup_dependency(&from, &Reference::parse(x)))
+            .filter(|x| !x.1)
+            .map(|x| x.0)
+            .collect()
+    }
+
+    // Titles making up a dependency cycle starting in the current list
+    pub fn find_dependency_cycle(&self) -> Option<Vec<String>> {
+        let from = self.cur_loaded_list().path.clone();
+        let mut starts = vec![];
+        dependency::collect_ids(&self.cur_loaded_list().contents, &mut starts);
+        let mut finished = HashSet::new();
+        for id in starts {
+            let start = Reference { list: None, id };
+            if let Some(cycle) = self.visit_dependency(&from, &start, &mut vec![], &mut finished) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    // Depth first search, `trail` holds (key, title) of the items on the current path
+    fn visit_dependency(&self, from: &str, reference: &Reference, trail: &mut Vec<(String, String)>,
+                        finished: &mut HashSet<String>) -> Option<Vec<String>> {
+        let list_path = self.canonical_path(reference.list.as_ref().map_or(from, |x| x));
+        let key = format!("{}#{}", list_path, reference.id);
+        if let Some(start) = trail.iter().position(|x| x.0 == key) {
+            let mut cycle: Vec<String> = trail[start..].iter().map(|x| x.1.clone()).collect();
+            cycle.push(trail[start].1.clone());
+            return Some(cycle);
+        }
+        if finished.contains(&key) { return None; }
+
+        let (title, _, depends_on) = self.lookup_dependency(from, reference)?;
+        trail.push((key.clone(), title));
+        for dependency in depends_on {
+            if let Some(cycle) = self.visit_dependency(&list_path, &Reference::parse(&dependency), trail, finished) {
+                return Some(cycle);
+            }
+        }
+        trail.pop();
+        finished.insert(key);
+        None
+    }
+
+    // Reports any cycles through the status message
+    pub fn check_dependencies(&mut self) {
+        if let Some(cycle) = self.find_dependency_cycle() {
+            self.message = Some(format!("Dependency cycle: {}", cycle.join(" -> ")));
+        }
     }
 
     pub fn has_items(&self) -> bool {
@@ -97,7 +546,7 @@ impl WindowState {
         unsafe {
             for i in 0 .. self.cur.borrow().len() - 1 {
                 let tmp = self.cur.borrow();
-                it = &mut (*it)[*tmp[i].borrow()].contents as *mut Vec<todo_list::TodoItem>;
+                it = &mut (&mut *it)[*tmp[i].borrow()].contents as *mut Vec<todo_list::TodoItem>;
             }
             &mut *it
         }
@@ -107,45 +556,244 @@ impl WindowState {
         Ok(&mut self.cur_parent_list()[*self.last_cur()?])
     }
 
+    // Moves the cursor back into the list after items were removed
+    pub fn clamp_cur(&self) {
+        let mut res = vec![];
+        let mut list = &self.cur_loaded_list().contents;
+        for i in self.cur_path() {
+            if list.is_empty() { break; }
+            let i = i.min(list.len() - 1);
+            res.push(i);
+            list = &list[i].contents;
+        }
+        self.set_cur(&res);
+    }
+
     pub fn reset_cur(&self) {
         self.cur.borrow_mut().clear();
         self.cur.borrow_mut().push(RefCell::from(0));
     }
 
+    pub fn cur_path(&self) -> Vec<usize> {
+        self.cur.borrow().iter().map(|x| *x.borrow()).collect()
+    }
+
     pub fn cur_depth(&self) -> usize {
         self.cur.borrow().len()
     }
 
+    pub fn hoist_path(&self) -> &[usize] {
+        &self.hoist
+    }
+
+    pub fn hoist_depth(&self) -> usize {
+        self.hoist.len()
+    }
+
+    // The hoisted item, if there is one
+    pub fn hoisted_item<'a>(&self) -> Option<&'a mut todo_list::TodoItem> {
+        if self.hoist.is_empty() { return None; }
+        let list = self.cur_loaded_list() as *mut todo_list::TodoList;
+        unsafe {(&mut *list).item_at(&self.hoist)}
+    }
+
+    // The items shown at the top level of the view
+    pub fn root_items<'a>(&self) -> &'a mut Vec<todo_list::TodoItem> {
+        match self.hoisted_item() {
+            Some(item) => &mut item.contents,
+            None => {
+                let list = self.cur_loaded_list() as *mut todo_list::TodoList;
+                unsafe {&mut (&mut *list).contents}
+            },
+        }
+    }
+
+    // Makes the current item the root of the view
+    pub fn hoist(&mut self) {
+        self.hoist = self.cur_path();
+        let first = self.first_visible(self.root_items()).unwrap_or(0);
+        self.push_cur(first);
+    }
+
+    // Back to the whole list, the cursor stays where it is
+    pub fn unhoist(&mut self) -> bool {
+        !::std::mem::take(&mut self.hoist).is_empty()
+    }
+
+    // The cursor can't leave the hoisted item
+    pub fn can_pop_cur(&self) -> bool {
+        self.cur_depth() > self.hoist.len() + 1
+    }
+
+    // Unhoists if the hoisted item is gone or the cursor was moved out of it (i.e. by a jump)
+    pub fn check_hoist(&mut self) {
+        if self.hoist.is_empty() { return; }
+        let cur = self.cur_path();
+        if self.hoisted_item().is_none() || cur.len() <= self.hoist.len() || cur[..self.hoist.len()] != self.hoist[..] {
+            self.hoist.clear();
+        }
+    }
+
+    pub fn is_visible(&self, item: &todo_list::TodoItem) -> bool {
+        if self.hide_completed && item.is_complete() {
+            return false;
+        }
+        match self.filter {
+            Some(ref filter) => filter.matches(&item.tags())
+                || item.contents.iter().any(|child| self.is_visible(child)),
+            None => true,
+        }
+    }
+
+    // Moves the item at `from` to be child `index` of the item at `parent` (placing the cursor on it),
+    // `parent` can't be inside of the item being moved
+    pub fn move_item(&self, from: &[usize], parent: &[usize], index: usize) -> Result<()> {
+        if parent.starts_with(from) { bail!("Can't move an item into itself") }
+        let (&from_index, from_parent) = match from.split_last() {
+            Some(x) => x,
+            None => bail!("Nothing to move"),
+        };
+        // later siblings (and everything under them) shift up once the item is removed
+        let mut parent = parent.to_vec();
+        let mut index = index;
+        let depth = from_parent.len();
+        if parent.len() > depth && parent[..depth] == *from_parent && parent[depth] > from_index {
+            parent[depth] -= 1;
+        } else if parent[..] == *from_parent && index > from_index {
+            index -= 1;
+        }
+
+        let list = self.cur_loaded_list();
+        let item = match list.take_at(from) {
+            Some(item) => item,
+            None => bail!("No item to move"),
+        };
+        if self.config.auto_tick_parents {
+            list.update_parent_ticks(from);
+        }
+        let siblings = match list.contents_at(&parent) {
+            Some(siblings) => siblings,
+            None => bail!("No item to move to"),
+        };
+        let index = index.min(siblings.len());
+        siblings.insert(index, item);
+        parent.push(index);
+        self.set_cur(&parent);
+        self.update_parent_ticks();
+        Ok(())
+    }
+
+    // Every shown item under the root of the view by its path, in the order they are drawn
+    pub fn visible_rows<'a>(&self) -> Vec<(Vec<usize>, &'a todo_list::TodoItem)> {
+        let mut rows = vec![];
+        self.collect_rows(self.root_items(), &mut self.hoist.clone(), &mut rows);
+        rows
+    }
+
+    fn collect_rows<'a>(&self, items: &'a [todo_list::TodoItem], path: &mut Vec<usize>, rows: &mut Vec<(Vec<usize>, &'a todo_list::TodoItem)>) {
+        for (i, item) in items.iter().enumerate() {
+            if !self.is_visible(item) { continue; }
+            path.push(i);
+            rows.push((path.clone(), item));
+            self.collect_rows(&item.contents, path, rows);
+            path.pop();
+        }
+    }
+
+    // Where the cursor is in `visible_rows`
+    pub fn cur_row(&self, rows: &[(Vec<usize>, &todo_list::TodoItem)]) -> Option<usize> {
+        let cur = self.cur_path();
+        rows.iter().position(|x| x.0 == cur)
+    }
+
+    // Moves the cursor by rows rather than between siblings, so it goes in and out of subtrees
+    pub fn move_cur_rows(&self, amount: usize, down: bool) {
+        let rows = self.visible_rows();
+        if let Some(row) = self.cur_row(&rows) {
+            let row = if down {(row + amount).min(rows.len() - 1)} else {row.saturating_sub(amount)};
+            self.set_cur(&rows[row].0);
+        }
+    }
+
+    pub fn first_visible(&self, list: &[todo_list::TodoItem]) -> Option<usize> {
+        list.iter().position(|item| self.is_visible(item))
+    }
+
+    // Wraps around, if nothing else is visible then we stay put
+    fn step_visible(&self, list: &[todo_list::TodoItem], from: usize, forward: bool) -> usize {
+        let len = list.len();
+        for i in 1 .. len + 1 {
+            let index = if forward {(from + i) % len} else {(from + len - i % len) % len};
+            if self.is_visible(&list[index]) {
+                return index;
+            }
+        }
+        from
+    }
+
     pub fn move_cur_down(&self, amount: usize) -> Result<()> {
-        let list_len = self.cur_parent_list().len();
-        if list_len > 0 {
+        let list = self.cur_parent_list();
+        if !list.is_empty() {
             let last = self.last_cur()?;
-            // handling overflow as well
-            if amount <= !0 && *last <= !0 - amount && *last < self.cur_parent_list().len() - 1 {
-                *last += amount;
-            } else {
-                *last = 0;
+            for _ in 0 .. amount {
+                *last = self.step_visible(list, *last, true);
             }
         }
         Ok(())
     }
 
     pub fn move_cur_up(&self, amount: usize) -> Result<()> {
-        let list_len = self.cur_parent_list().len();
-        if list_len > 0 {
+        let list = self.cur_parent_list();
+        if !list.is_empty() {
             let last = self.last_cur()?;
-            // handling underflow
-            if *last >= amount {
-                *last -= amount;
-            } else  {
-                *last = list_len - 1;
+            for _ in 0 .. amount {
+                *last = self.step_visible(list, *last, false);
             }
         }
         Ok(())
     }
 
+    // Moves the cursor off an item that is no longer shown (i.e. it was just ticked while hiding completed items)
+    // onto the next shown sibling, or the previous one, or else its parent
+    pub fn check_cur_visible(&self) {
+        let cur = self.cur_path();
+        let mut res = vec![];
+        let mut list = &self.cur_loaded_list().contents;
+        for (depth, &i) in cur.iter().enumerate() {
+            let item = match list.get(i) {
+                Some(item) => item,
+                None => return,
+            };
+            if depth < self.hoist.len() || self.is_visible(item) {
+                res.push(i);
+                list = &item.contents;
+                continue;
+            }
+            let sibling = (i + 1 .. list.len()).find(|&x| self.is_visible(&list[x]))
+                .or_else(|| (0 .. i).rev().find(|&x| self.is_visible(&list[x])));
+            match sibling {
+                Some(x) => res.push(x),
+                // nothing is shown at the top, so there is nowhere better to be
+                None if res.len() <= self.hoist.len() => return,
+                None => {},
+            }
+            break;
+        }
+        if res != cur {
+            self.set_cur(&res);
+        }
+    }
+
+    // Places the cursor on the first visible top level item (under the hoisted one)
+    pub fn reset_cur_visible(&self) {
+        let mut cur = self.hoist.clone();
+        cur.push(self.first_visible(self.root_items()).unwrap_or(0));
+        self.set_cur(&cur);
+    }
+
     pub fn set_cur(&self, values: &[usize]) {
-        self.reset_cur();
+        if values.is_empty() { return self.reset_cur(); }
+        self.cur.borrow_mut().clear();
         for val in values {
             self.push_cur(*val);
         }
@@ -1,24 +1,57 @@
 use termion;
 use termion::event::{Key};
-use termion::{color, style};
+use termion::style;
 use termion::input::{MouseTerminal, TermRead};
 use termion::screen::AlternateScreen;
 use termion::raw::{IntoRawMode, RawTerminal};
-use std::io::{stdout, Write, Stdout, stdin};
+use std::io::{stdout, ErrorKind, Write, Stdout, stdin};
 use std::io::Result as IOResult;
+use std::process::Command;
 use std;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 use window_state::WindowState;
+use input_history::{InputHistory, InputKind};
 use errors::*;
 use todo_list;
+use tags;
+use link;
+use duration;
+use util;
+use theme;
+use symbols;
+
+// The same sequences `MouseTerminal` uses
+const ENABLE_MOUSE: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+const DISABLE_MOUSE: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+// Part of the screen, x and y start at 1 like `termion::cursor::Goto`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
 
 pub struct WindowView {
     out: MouseTerminal<AlternateScreen<RawTerminal<Stdout>>>,
     pub size: (u16, u16),
+    // the list whose tags are offered when completing with tab in item prompts
+    pub tag_source: Option<Rc<RefCell<todo_list::TodoList>>>,
+    // the rows of the list being printed
+    lines: Vec<String>,
+    // the next screen being drawn, as (column, text) pieces for each row
+    frame: Vec<Vec<(u16, String)>>,
+    // what the terminal shows, `None` when it isn't known and everything has to be redrawn
+    screen: Option<Vec<Vec<(u16, String)>>>,
 }
 
+// Anything written straight to the terminal (i.e. prompts) means the screen has to be redrawn completely
 impl Write for WindowView {
     fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        self.screen = None;
         self.out.write(buf)
     }
 
@@ -33,9 +66,73 @@ impl WindowView {
         Ok(WindowView {
             out: MouseTerminal::from(AlternateScreen::from(stdout().into_raw_mode()?)),
             size: size,
+            tag_source: None,
+            lines: vec![],
+            frame: vec![],
+            screen: None,
         })
     }
 
+    // Adds `text` to the next frame, the row it is on is redrawn if it changed since the last frame
+    pub fn put(&mut self, x: u16, y: u16, text: String) {
+        let row = y.saturating_sub(1) as usize;
+        if self.frame.len() <= row {
+            self.frame.resize(row + 1, vec![]);
+        }
+        self.frame[row].push((x, text));
+    }
+
+    // Writes out the rows of the frame that differ from what is on the screen
+    pub fn present(&mut self) -> Result<()> {
+        let mut frame = ::std::mem::take(&mut self.frame);
+        frame.resize(self.size.1 as usize, vec![]);
+        let screen = match self.screen.take() {
+            Some(screen) => screen,
+            None => {
+                write!(self.out, "{}", termion::clear::All)?;
+                vec![]
+            },
+        };
+        for (row, pieces) in frame.iter().enumerate() {
+            if screen.get(row).map_or(pieces.is_empty(), |x| x == pieces) { continue; }
+            write!(self.out, "{}{}", termion::cursor::Goto(1, row as u16 + 1), termion::clear::CurrentLine)?;
+            for &(x, ref text) in pieces {
+                write!(self.out, "{}{}", termion::cursor::Goto(x, row as u16 + 1), text)?;
+            }
+        }
+        self.screen = Some(frame);
+        Ok(())
+    }
+
+    // Forgets what is on the screen so the next frame is drawn completely (i.e. after a resize)
+    pub fn invalidate(&mut self) {
+        self.screen = None;
+    }
+
+    // Places the (hidden) cursor without touching the screen
+    pub fn move_cursor(&mut self, x: u16, y: u16) -> Result<()> {
+        write!(self.out, "{}", termion::cursor::Goto(x, y))?;
+        Ok(())
+    }
+
+    // Asks a yes/no question on the bottom line, enter means yes
+    pub fn confirm(&mut self, prompt: &str) -> Result<bool> {
+        let height = self.size.1 - 1;
+        write!(self, "{}{}{} [Y/n] ", termion::cursor::Goto(1, height), termion::clear::CurrentLine, prompt)?;
+        self.flush()?;
+        for c in stdin().keys() {
+            match c {
+                Ok(Key::Char('y')) | Ok(Key::Char('Y')) | Ok(Key::Char('\n')) => return Ok(true),
+                Ok(Key::Char('n')) | Ok(Key::Char('N')) | Ok(Key::Esc) => return Ok(false),
+                // a resize interrupted the wait for input
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(err) => return Err(err.into()),
+                Ok(_) => {},
+            }
+        }
+        Ok(false)
+    }
+
     pub fn calc_size(&mut self) -> Result<()> {
         self.size = termion::terminal_size()?;
         Ok(())
@@ -43,79 +140,235 @@ impl WindowView {
 
     fn get_color(val: usize) -> String {
         match val {
-            0 ..= 33 => color::Fg(color::Red).to_string(),
-            34 ..= 66 => color::Fg(color::Yellow).to_string(),
-            _ => color::Fg(color::Green).to_string(),
+            0 ..= 33 => theme::get().progress_low.clone(),
+            34 ..= 66 => theme::get().progress_mid.clone(),
+            _ => theme::get().progress_high.clone(),
         }
     }
 
-    pub fn print_out_list(&mut self, win: &mut WindowState, offset: usize, mut amount: usize) -> Result<()> {
-        self.clear()?;
+    // The title (and the tabs) above the items of a pane
+    pub fn header_rows(win: &WindowState) -> usize {
+        if win.tab_count() > 1 {2} else {1}
+    }
+
+    // The rows of `rect` left for items
+    pub fn item_rows(win: &WindowState, rect: Rect) -> usize {
+        (rect.height as usize).saturating_sub(Self::header_rows(win))
+    }
+
+    // Prints the list into `rect` starting `offset` rows down, the focused pane of a split has a bold title
+    pub fn print_out_list(&mut self, win: &mut WindowState, offset: usize, rect: Rect, focused: bool) -> Result<()> {
+        self.lines.clear();
+        let mut amount = rect.height as usize;
         if amount == 0 { return Ok(()); }
 
+        if win.tab_count() > 1 {
+            self.print_tab_bar(win, rect.width as usize);
+            amount -= 1;
+        }
+
         let cur_list = win.cur_loaded_list();
-        let currently_ticked_off = cur_list.contents.iter().filter(|x| x.ticked_off).count();
-        let percentage = if cur_list.contents.len() > 0 { (100 * currently_ticked_off) / cur_list.contents.len() } else { 0 };
-        let title = format!("{bold}== {file}{flag1}{flag2} [{cur}/{total}, {color}{percentage}%{reset}{bold}] =={reset}", 
-                        bold = style::Bold,
+        // when hoisted everything is about the hoisted item
+        let ((currently_ticked_off, total), (done_weight, total_weight), estimates) = match win.hoisted_item() {
+            Some(item) => (item.progress(), item.weighted_progress(win.config.estimate_weighted_progress), item.estimates()),
+            None => (cur_list.progress(), cur_list.weighted_progress(win.config.estimate_weighted_progress), cur_list.estimates()),
+        };
+        let percentage = (100 * done_weight).checked_div(total_weight).unwrap_or(0) as usize;
+        let mut filter = win.filter.as_ref().map_or(String::new(), |f| format!(" ({})", f.source));
+        if win.hide_completed {
+            filter.push_str(" (hiding done)");
+        }
+        let estimates = match estimates {
+            (_, 0) => String::new(),
+            (remaining, total) => format!(", {}/{} left", duration::format_duration(remaining), duration::format_duration(total)),
+        };
+        let timer = match cur_list.running_timer() {
+            Some(item) => format!(", timing '{}' {}", item.title.chars().take(20).collect::<String>(),
+                                  duration::format_duration(item.time_log.last().map_or(0, |x| x.minutes(None)))),
+            None => String::new(),
+        };
+        let title = format!("{bold}== {file}{flag1}{flag2}{filter} [{cur}/{total}, {color}{percentage}%{reset}{bold}{estimates}{timer}] =={reset}", 
+                        bold = if focused {theme::get().header.clone()} else {style::Faint.to_string()},
                         reset = style::Reset,
-                        file = cur_list.name,
-                        flag1 = if win.changes {"*"} else {""},
-                        flag2 = if win.destructive_changes {"!"} else {""},
+                        file = Self::breadcrumb(win),
+                        filter = filter,
+                        estimates = estimates,
+                        timer = timer,
+                        flag1 = if win.changes() {"*"} else {""},
+                        flag2 = if win.destructive_changes() {"!"} else {""},
                         cur = currently_ticked_off,
-                        total = cur_list.contents.len(),
+                        total = total,
                         color = Self::get_color(percentage),
                         percentage = percentage);
-        // Technical Debt: This is hard coded, maybe make a method to figure this out by stripping ansi
-        let w = self.size.0;
-        write!(self, "{}", str::repeat(" ", (w as usize - (title.len() - 25)) / 2))?;
-        write!(self, "{}\n\r", title)?;
-        amount -= 1;
+        let padding = (rect.width as usize).saturating_sub(util::visible_len(&title)) / 2;
+        self.lines.push(format!("{}{}", str::repeat(" ", padding), title));
+        amount = amount.saturating_sub(1);
 
-        for (i, item) in cur_list.contents.iter().skip(offset).enumerate() {
-            if amount == 0 { break; }
-            self.print_item(win, item, 0, *win.cur(0)? == i + offset, &mut amount)?;
+        let cur = win.cur_path();
+        for (path, item) in win.visible_rows().into_iter().skip(offset).take(amount) {
+            self.print_sub_item(win, item, path == cur, path.len() - 1)?;
         }
-        Ok(())
+        self.draw_lines(rect)
     }
 
-    fn print_sub_item(&mut self, item: &todo_list::TodoItem, at_pos: bool, depth: usize) -> Result<()> {
-        write!(self,
-            "{}{} [{}] {}\n\r",
-            str::repeat("    ", depth),
-            if at_pos {"→"} else {" "},
-            if item.ticked_off {"✓"} else {" "},
-            item.title,
-        )?;
-        Ok(())
+    // The list name followed by the titles down to the hoisted item
+    fn breadcrumb(win: &WindowState) -> String {
+        let list = win.cur_loaded_list();
+        let mut res = list.name.clone();
+        let mut items = &list.contents;
+        for &i in win.hoist_path() {
+            if let Some(item) = items.get(i) {
+                let title: String = item.title.chars().take(20).collect();
+                res.push_str(&format!(" {} {}", symbols::breadcrumb(), title));
+                items = &item.contents;
+            }
+        }
+        res
     }
 
-    // depth starts at 0
-    fn print_item(&mut self, win: &WindowState, item: &todo_list::TodoItem, depth: usize, could_select: bool, amount: &mut usize) -> Result<()> {
-        self.print_sub_item(item, could_select && win.cur_depth() == depth + 1, depth)?;
-        *amount -= 1;
-        for (i, child) in item.contents.iter().enumerate() {
-            if *amount == 0 { break; }
-            self.print_item(win, child, depth + 1, win.cur_depth() != depth + 1 && could_select && *win.cur(depth + 1)? == i, amount)?;
+    // Writes out the printed rows cut to fit in `rect`, the rest of it is cleared
+    fn draw_lines(&mut self, rect: Rect) -> Result<()> {
+        let lines = ::std::mem::take(&mut self.lines);
+        for row in 0 .. rect.height {
+            let line = lines.get(row as usize).map_or(String::new(), |x| util::truncate_visible(x, rect.width as usize));
+            let padding = (rect.width as usize).saturating_sub(util::visible_len(&line));
+            self.put(rect.x, rect.y + row, format!("{}{}{}", line, str::repeat(" ", padding), style::Reset));
         }
+        Ok(())
+    }
 
+    // i.e. ` NORMAL  modified  3 Foo › 1 Bar  "a 2  <message>` across the whole row
+    pub fn print_status_line(&mut self, win: &WindowState, row: u16, width: u16) -> Result<()> {
+        let mode = if win.hoist_depth() > 0 {"HOIST"} else {"NORMAL"};
+        let save_state = if win.destructive_changes() {"deleted"} else if win.changes() {"modified"} else {"saved"};
+        let mut position = vec![];
+        let mut items = &win.cur_loaded_list().contents;
+        for i in win.cur_path() {
+            match items.get(i) {
+                Some(item) => {
+                    let title: String = item.title.chars().take(15).collect();
+                    position.push(format!("{} {}", i + 1, title));
+                    items = &item.contents;
+                },
+                None => break,
+            }
+        }
+        let mut pending = String::new();
+        if let Some(name) = win.pending_register {
+            pending.push_str(&format!("\"{}", name));
+        }
+        if let Some(count) = win.pending_count {
+            pending.push_str(&count.to_string());
+        }
+        let bar = &theme::get().status_line;
+        let message = win.message.as_ref().map_or(String::new(), |x| format!("{}{} {}{}", style::Reset, x, style::Reset, bar));
+        let line = format!(" {}{}{}{}  {}  {}  {}  {}", style::Bold, mode, style::Reset, bar, save_state, position.join(&format!(" {} ", symbols::breadcrumb())), pending, message);
+        let line = util::truncate_visible(&line, width as usize);
+        let padding = (width as usize).saturating_sub(util::visible_len(&line));
+        self.put(1, row, format!("{}{}{}{}", bar, line, str::repeat(" ", padding), style::Reset));
         Ok(())
     }
 
-    fn forward_word(pos: &mut usize, text: &String) {
-        for ch in text.chars().skip(*pos + 1) {
-            if ch == ' ' { break; }
-            *pos += 1;
+    // i.e. ` inbox* | work ` with the current tab inverted
+    fn print_tab_bar(&mut self, win: &WindowState, width: usize) {
+        let mut line = String::new();
+        let mut used = 0;
+        for (i, (name, changed)) in win.tab_names().into_iter().enumerate() {
+            let name: String = name.chars().take(20).collect();
+            let label = format!(" {}{} ", name, if changed {"*"} else {""});
+            let len = util::visible_len(&label) + 1;
+            if used + len > width { break; }
+            used += len;
+            if i == win.cur_tab() {
+                line.push_str(&format!("{}{}{}|", theme::get().current_tab, label, style::Reset));
+            } else {
+                line.push_str(&format!("{}|", label));
+            }
         }
+        self.lines.push(line);
     }
 
-    fn backward_word(pos: &mut usize, text: &String) {
-        for ch in text.chars().take(*pos) {
-            if ch == ' ' { break; }
-            *pos -= 1;
+    fn priority_marker(priority: todo_list::Priority) -> String {
+        use todo_list::Priority;
+        match priority {
+            Priority::None => String::new(),
+            Priority::Low => format!("{}!{} ", theme::get().priority_low, style::Reset),
+            Priority::Medium => format!("{}!!{} ", theme::get().priority_medium, style::Reset),
+            Priority::High => format!("{}!!!{} ", theme::get().priority_high, style::Reset),
+            Priority::Critical => format!("{}!!!!{} ", theme::get().priority_critical, style::Reset),
         }
     }
 
+    fn print_sub_item(&mut self, win: &WindowState, item: &todo_list::TodoItem, at_pos: bool, depth: usize) -> Result<()> {
+        let theme = theme::get();
+        // statuses that were removed from the config still need to show up
+        let symbol = win.config.status(item.status_name()).map_or("?".to_owned(), |x| symbols::status(&x.symbol));
+        let progress = if item.contents.is_empty() {
+            String::new()
+        } else {
+            let (done, total) = item.progress();
+            let (done_weight, total_weight) = item.weighted_progress(win.config.estimate_weighted_progress);
+            let percentage = (100 * done_weight).checked_div(total_weight).unwrap_or(0) as usize;
+            format!(" {}[{}/{}]{}", Self::get_color(percentage), done, total, style::Reset)
+        };
+        let estimates = match item.estimates() {
+            (_, 0) => String::new(),
+            (remaining, total) if item.contents.is_empty() || remaining == total =>
+                format!(" {}({}){}", theme.detail, duration::format_duration(total), style::Reset),
+            (remaining, total) => format!(" {}({}/{}){}", theme.detail,
+                duration::format_duration(remaining), duration::format_duration(total), style::Reset),
+        };
+        let logged = match item.logged_total(None) {
+            0 => String::new(),
+            minutes => format!(" {}[{} spent]{}", theme.detail, duration::format_duration(minutes), style::Reset),
+        };
+        let today = ::chrono::Local::now().date_naive();
+        let due = match item.due {
+            Some(due) if item.is_overdue() => format!(" {}due {}{}", theme.overdue, due, style::Reset),
+            Some(due) if due == today && !item.ticked_off => format!(" {}due today{}", theme.due_today, style::Reset),
+            Some(due) => format!(" {}due {}{}", theme.detail, due, style::Reset),
+            None => String::new(),
+        };
+        let recur = item.recur.as_ref().map_or(String::new(), |x| format!(" {}({}){}", theme.detail, x, style::Reset));
+        let blocked = match win.blocked_by(item) {
+            ref blocked if blocked.is_empty() => String::new(),
+            ref blocked if blocked.len() == 1 => format!(" {}(blocked by '{}'){}", theme.blocked, blocked[0], style::Reset),
+            blocked => format!(" {}(blocked by '{}' +{}){}", theme.blocked, blocked[0], blocked.len() - 1, style::Reset),
+        };
+        let timing = if item.is_timing() {format!(" {}(timing){}", theme.timing, style::Reset)} else {String::new()};
+        let title = tags::highlight_tags(&link::highlight_links(&item.title));
+        let title = if item.ticked_off {format!("{}{}{}", theme.ticked, title.replace(&style::Reset.to_string(), &format!("{}{}", style::Reset, theme.ticked)), style::Reset)} else {title};
+        let line = format!(
+            "{}{} [{}] {}{}{}{}{}{}{}{}{}",
+            str::repeat("    ", depth - win.hoist_depth()),
+            if at_pos {format!("{}{}{}", theme.cursor, symbols::cursor(), style::Reset)} else {" ".to_owned()},
+            symbol,
+            Self::priority_marker(item.priority),
+            title,
+            blocked,
+            due,
+            recur,
+            progress,
+            estimates,
+            logged,
+            timing,
+        );
+        // the rest of the row is padded in the same style when drawn
+        let line = if at_pos {format!("{}{}", theme.cursor_line, line.replace(&style::Reset.to_string(), &format!("{}{}", style::Reset, theme.cursor_line)))} else {line};
+        self.lines.push(line);
+        Ok(())
+    }
+
+    fn forward_word(pos: &mut usize, text: &str) {
+        let rest = &text[*pos..];
+        let start = rest.len() - rest.trim_start_matches(' ').len();
+        *pos += rest[start..].find(' ').map_or(rest.len(), |x| start + x);
+    }
+
+    fn backward_word(pos: &mut usize, text: &str) {
+        *pos = text[..*pos].trim_end_matches(' ').rfind(' ').map_or(0, |x| x + 1);
+    }
+
     fn split_path(path: &str) -> (Option<&str>, &str) {
         match path.rfind(std::path::is_separator) {
             Some(pos) => (Some(&path[..pos]), &path[pos + 1..]),
@@ -154,7 +407,22 @@ impl WindowView {
         res
     }
 
-    pub fn get_user_input_buf(&mut self, prompt: &str, buf: &str, pos: Option<usize>, use_path: bool) -> Result<Option<String>> {
+    // Completes the `#tag` (or `!#tag` for filters) being typed at the end of the buffer
+    fn get_tag_completion(&self, buffer: &str) -> Vec<(String, String)> {
+        let start = buffer.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = buffer[start..].trim_start_matches('!');
+        let prefix = &buffer[..buffer.len() - word.len()];
+        if !word.starts_with('#') { return vec![]; }
+        let partial = word[1..].to_lowercase();
+        // only gathered once tab is pressed as it goes through the whole list
+        let tags = self.tag_source.as_ref().map_or(vec![], |x| x.borrow().all_tags());
+        tags.into_iter()
+            .filter(|tag| tag.starts_with(&partial))
+            .map(|tag| (tag.clone(), format!("{}#{}", prefix, tag)))
+            .collect()
+    }
+
+    pub fn get_user_input_buf(&mut self, prompt: &str, buf: &str, pos: Option<usize>, kind: InputKind) -> Result<Option<String>> {
         self.set_cursor(true)?;
         self.flush()?;
         let mut buf = buf.to_owned();
@@ -164,56 +432,126 @@ impl WindowView {
         let mut current_index = 0usize;
         let mut buffer_changed = true;
 
+        let mut history = InputHistory::load();
+        // history_index == entries.len() means we are editing our own buffer
+        let mut history_index = history.entries(kind).len();
+        let mut saved_buffer = buf.clone();
+        // (query, index of current match)
+        let mut search: Option<(String, Option<usize>)> = None;
+
         let height = self.size.1 - 1;
         write!(self, "{}{}: {}", termion::cursor::Goto(1, height), prompt, buf)?;
         self.flush()?;
         for c in stdin().keys() {
-            match c {
-                Ok(Key::Ctrl('c')) | Ok(Key::Ctrl('q')) => return Ok(None),
-                Ok(Key::Char('\n')) => break,
-                Ok(Key::Backspace) => {
-                    if buf.len() > 0 && cur_pos > 0 {
-                        buf.remove(cur_pos - 1);
-                        cur_pos -= 1;
+            if let Some((mut query, mut found)) = search.take() {
+                let len = history.entries(kind).len();
+                match c {
+                    Ok(Key::Ctrl('c')) | Ok(Key::Ctrl('g')) | Ok(Key::Esc) => {},
+                    Ok(Key::Ctrl('r')) => {
+                        found = history.search(kind, &query, found.unwrap_or(len)).or(found);
+                        search = Some((query, found));
+                    },
+                    Ok(Key::Backspace) => {
+                        query.pop();
+                        found = history.search(kind, &query, len);
+                        search = Some((query, found));
+                    },
+                    Ok(Key::Char('\n')) => {
+                        if let Some(i) = found {
+                            buf = history.entries(kind)[i].clone();
+                            cur_pos = buf.len();
+                            history_index = i;
+                            buffer_changed = true;
+                        }
+                    },
+                    Ok(Key::Char(c)) => {
+                        query.push(c);
+                        found = history.search(kind, &query, found.map_or(len, |i| i + 1));
+                        search = Some((query, found));
+                    },
+                    _ => search = Some((query, found)),
+                }
+            } else {
+                match c {
+                    Ok(Key::Ctrl('c')) | Ok(Key::Ctrl('q')) => return Ok(None),
+                    Ok(Key::Char('\n')) => break,
+                    Ok(Key::Backspace) => {
+                        if let Some(ch) = buf[..cur_pos].chars().next_back() {
+                            cur_pos -= ch.len_utf8();
+                            buf.remove(cur_pos);
+                            buffer_changed = true;
+                        }
+                    },
+                    // cur_pos is a byte index, so step over whole characters
+                    Ok(Key::Left) if cur_pos > 0 => cur_pos -= buf[..cur_pos].chars().next_back().map_or(1, |x| x.len_utf8()),
+                    Ok(Key::Right) if cur_pos < buf.len() => cur_pos += buf[cur_pos..].chars().next().map_or(1, |x| x.len_utf8()),
+                    Ok(Key::Alt('b')) => Self::backward_word(&mut cur_pos, &buf),
+                    Ok(Key::Alt('f')) => Self::forward_word(&mut cur_pos, &buf),
+                    Ok(Key::Up) | Ok(Key::Ctrl('p')) if history_index > 0 => {
+                        if history_index == history.entries(kind).len() {
+                            saved_buffer = buf.clone();
+                        }
+                        history_index -= 1;
+                        buf = history.entries(kind)[history_index].clone();
+                        cur_pos = buf.len();
                         buffer_changed = true;
-                    }
-                },
-                Ok(Key::Left) if cur_pos > 0 => cur_pos -= 1,
-                Ok(Key::Right) if cur_pos < buf.len() => cur_pos += 1,
-                Ok(Key::Up) => Self::backward_word(&mut cur_pos, &buf),
-                Ok(Key::Down) => Self::forward_word(&mut cur_pos, &buf),
-                Ok(Key::Char('\t')) if use_path => {
-                    if buffer_changed {
-                        current_choices = Self::get_path_completion(&buf);
-                        current_index = 0;
-                        old_buffer = buf.clone();
-                        buf = current_choices[current_index].1.to_owned();
-                    } else {
-                        if current_index == !0 || current_index >= current_choices.len() - 1 {
+                    },
+                    Ok(Key::Down) | Ok(Key::Ctrl('n')) => {
+                        let len = history.entries(kind).len();
+                        if history_index < len {
+                            history_index += 1;
+                            buf = if history_index == len {saved_buffer.clone()} else {history.entries(kind)[history_index].clone()};
+                            cur_pos = buf.len();
+                            buffer_changed = true;
+                        }
+                    },
+                    Ok(Key::Ctrl('r')) => search = Some((String::new(), None)),
+                    Ok(Key::Char('\t')) if kind != InputKind::Goto && kind != InputKind::Name => {
+                        if buffer_changed {
+                            current_choices = if kind == InputKind::Path {
+                                Self::get_path_completion(&buf)
+                            } else {
+                                self.get_tag_completion(&buf)
+                            };
+                            if current_choices.is_empty() { continue; }
                             current_index = 0;
+                            old_buffer = buf.clone();
+                            buf = current_choices[current_index].1.to_owned();
+                            buffer_changed = false;
                         } else {
-                            current_index += 1;
+                            if current_index == !0 || current_index >= current_choices.len() - 1 {
+                                current_index = 0;
+                            } else {
+                                current_index += 1;
+                            }
+                            buf = current_choices[current_index].1.to_owned();
                         }
-                        buf = current_choices[current_index].1.to_owned();
-                    }
-                },
-                Ok(Key::Char(c)) => {
-                    buf.push(c);
-                    cur_pos += 1;
-                    buffer_changed = true;
-                },
-                Ok(Key::Esc) => {
-                    if !buffer_changed {
+                        cur_pos = buf.len();
+                    },
+                    Ok(Key::Char(c)) => {
+                        buf.insert(cur_pos, c);
+                        cur_pos += c.len_utf8();
+                        buffer_changed = true;
+                    },
+                    Ok(Key::Esc) if !buffer_changed => {
                         buf = old_buffer.clone();
                         buffer_changed = true;
-                    }
-                },
-                _ => {},
+                    },
+                    _ => {},
+                }
             }
-            write!(self, "{}{}{}: {}", termion::clear::CurrentLine, 
-                termion::cursor::Left(!0), prompt, buf)?;
-            if buf.len() > cur_pos {
-                write!(self, "{}", termion::cursor::Left(buf.len() as u16 - cur_pos as u16))?;
+
+            if let Some((ref query, found)) = search {
+                let shown = found.map_or("", |i| &history.entries(kind)[i]);
+                write!(self, "{}{}(reverse-i-search)`{}': {}", termion::clear::CurrentLine,
+                    termion::cursor::Left(!0), query, shown)?;
+            } else {
+                write!(self, "{}{}{}: {}", termion::clear::CurrentLine,
+                    termion::cursor::Left(!0), prompt, buf)?;
+                let after = util::visible_len(&buf[cur_pos..]);
+                if after > 0 {
+                    write!(self, "{}", termion::cursor::Left(after as u16))?;
+                }
             }
             self.flush()?;
         }
@@ -221,11 +559,15 @@ impl WindowView {
         self.set_cursor(false)?;
         self.flush()?;
 
+        history.push(kind, &buf);
+        // Losing history isn't worth failing the input over
+        let _ = history.save();
+
         Ok(Some(buf))
     }
 
-    pub fn get_user_input(&mut self, prompt: &str, use_path: bool) -> Result<Option<String>> {
-        self.get_user_input_buf(prompt, "", None, use_path)
+    pub fn get_user_input(&mut self, prompt: &str, kind: InputKind) -> Result<Option<String>> {
+        self.get_user_input_buf(prompt, "", None, kind)
     }
 
     pub fn clear(&mut self) -> Result<()> {
@@ -235,9 +577,24 @@ impl WindowView {
 
     pub fn set_cursor(&mut self, enabled: bool) -> Result<()> {
         if enabled {
-            write!(self, "{}", termion::cursor::Show)?;
+            write!(self.out, "{}", termion::cursor::Show)?;
         } else {
-            write!(self, "{}", termion::cursor::Hide)?;
+            write!(self.out, "{}", termion::cursor::Hide)?;
+        }
+        Ok(())
+    }
+
+    // Hands the terminal over to another program (i.e. $EDITOR) until it exits
+    pub fn run_external(&mut self, command: &mut Command) -> Result<()> {
+        write!(self, "{}{}{}", DISABLE_MOUSE, termion::screen::ToMainScreen, termion::cursor::Show)?;
+        self.flush()?;
+        self.out.suspend_raw_mode()?;
+        let status = command.status();
+        self.out.activate_raw_mode()?;
+        write!(self, "{}{}", termion::screen::ToAlternateScreen, ENABLE_MOUSE)?;
+        self.flush()?;
+        if !status.chain_err(|| "Failed to run command")?.success() {
+            bail!("Command exited with an error");
         }
         Ok(())
     }